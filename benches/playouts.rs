@@ -0,0 +1,44 @@
+// Rough timing of the hot paths used by search and simulations.
+// Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rust_nmm::nmm::{Color, Game, GameResult, NmmGame};
+
+fn playouts(games: u32) -> u64 {
+    let mut state: u64 = 1;
+    let mut positions = 0;
+    for _ in 0..games {
+        let mut game = Game::new();
+        while game.result() == GameResult::Ongoing && game.move_log().len() < 300 {
+            let moves = game.legal_moves();
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            game.action(moves[(state >> 33) as usize % moves.len()])
+                .unwrap();
+            black_box(game.count_pieces(Color::White) + game.count_pieces(Color::Black));
+            positions += 1;
+        }
+    }
+    positions
+}
+
+fn main() {
+    let start = Instant::now();
+    let nodes = black_box(Game::new()).perft(4);
+    let elapsed = start.elapsed();
+    println!(
+        "perft(4): {nodes} nodes in {elapsed:?} ({:.0} nodes/s)",
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+
+    let start = Instant::now();
+    let positions = playouts(black_box(200));
+    let elapsed = start.elapsed();
+    println!(
+        "playouts: {positions} positions in {elapsed:?} ({:.0} positions/s)",
+        positions as f64 / elapsed.as_secs_f64()
+    );
+}
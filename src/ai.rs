@@ -0,0 +1,505 @@
+// Computer opponent for Nine Men's Morris
+// Searches the tree of legal actions with negamax and alpha-beta pruning.
+// Only the public API of `Game` is used, so the evaluation can be replaced freely.
+
+use std::time::{Duration, Instant};
+
+use crate::nmm::{Action, Color, DrawReason, Game, GameResult, NmmGame, Player};
+
+// Larger than any evaluation, so that won positions always dominate.
+const WIN_SCORE: i32 = 1_000_000;
+
+// Iterative deepening stops here even if time is left, e.g. when the game ends soon anyway.
+const MAX_TIMED_DEPTH: u32 = 64;
+
+/// Playing strength presets for `ai_action`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AiLevel {
+    /// Looks one ply ahead and often plays a random action instead.
+    /// Without the `rand` feature it never plays at random, i.e. it is a plain one ply search.
+    Easy,
+    /// Looks three plies ahead and now and then plays a random action.
+    /// Without the `rand` feature it never plays at random.
+    Medium,
+    /// Looks five plies ahead and always plays the best action it finds.
+    Hard,
+}
+
+impl AiLevel {
+    // search depth and the chance in percent of playing a random action instead
+    fn settings(self) -> (u32, u32) {
+        match self {
+            AiLevel::Easy => (1, 40),
+            AiLevel::Medium => (3, 10),
+            AiLevel::Hard => (5, 0),
+        }
+    }
+}
+
+/// Picks an action for the player to move at the given strength, see `AiLevel`.
+/// Random actions need the `rand` feature and use the thread's generator, see
+/// `ai_action_with_rng` for reproducible games. Without the feature every level plays its
+/// best action. Returns `None` if the game is over or there is nothing to do.
+pub fn ai_action(game: &Game, level: AiLevel) -> Option<Action> {
+    #[cfg(feature = "rand")]
+    return ai_action_with_rng(game, level, &mut rand::rng());
+    #[cfg(not(feature = "rand"))]
+    best_action(game, level.settings().0)
+}
+
+/// Like `ai_action`, but draws the random actions from `rng`, so a seeded generator gives
+/// reproducible games.
+#[cfg(feature = "rand")]
+pub fn ai_action_with_rng(
+    game: &Game,
+    level: AiLevel,
+    rng: &mut impl rand::RngCore,
+) -> Option<Action> {
+    use rand::Rng;
+
+    let (depth, random_percent) = level.settings();
+    if rng.random_range(0..100) < random_percent {
+        return random_action(game, rng);
+    }
+    best_action(game, depth)
+}
+
+/// Returns the best action for the player to move, searching `depth` plies deep.
+/// Removals after a closed mill count as plies of their own.
+/// Returns `None` if the game is over or there is nothing to do.
+pub fn best_action(game: &Game, depth: u32) -> Option<Action> {
+    if game.result() != GameResult::Ongoing {
+        return None;
+    }
+    search_root(game, depth, None)
+}
+
+/// Like `best_action`, but searches deeper and deeper until `budget` is used up and returns the
+/// result of the deepest search that finished. The clock is checked between root moves, so the
+/// budget may be exceeded by the time one root move takes.
+/// If not even the first search finishes, the first legal action is returned.
+pub fn best_action_timed(game: &Game, budget: Duration) -> Option<Action> {
+    if game.result() != GameResult::Ongoing {
+        return None;
+    }
+    let deadline = Instant::now() + budget;
+    let mut best = game.legal_moves().first().copied();
+    for depth in 1..=MAX_TIMED_DEPTH {
+        match search_root(game, depth, Some(deadline)) {
+            Some(action) => best = Some(action),
+            None => break,
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+    }
+    best
+}
+
+// Searches all root moves and returns the best one,
+// or `None` if there is none or the deadline passed before all were searched.
+fn search_root(game: &Game, depth: u32, deadline: Option<Instant>) -> Option<Action> {
+    let mut game = game.clone();
+    let player = game.to_move();
+    let mut best = None;
+    let mut alpha = -WIN_SCORE * 2;
+    let beta = WIN_SCORE * 2;
+    for action in game.legal_moves() {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+        let score = score_action(
+            &mut game,
+            action,
+            player,
+            depth.saturating_sub(1),
+            alpha,
+            beta,
+        );
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(action);
+        }
+    }
+    best
+}
+
+/// Returns the number of plies until the player to move loses against best play, or `None` if
+/// they can avoid a loss for `max_depth` plies. Removals count as plies of their own, and a game
+/// that is already lost for the player to move gives `Some(0)`.
+pub fn distance_to_loss(game: &Game, max_depth: u32) -> Option<u32> {
+    let score = negamax(&mut game.clone(), max_depth, -WIN_SCORE * 2, WIN_SCORE * 2);
+    // a loss found with `remaining` plies of depth left scores `-WIN_SCORE - remaining`
+    let remaining = (-WIN_SCORE).checked_sub(score)?;
+    u32::try_from(remaining)
+        .ok()
+        .map(|remaining| max_depth - remaining)
+}
+
+/// Scores every legal action by searching `depth` plies deep, like `best_action`, and returns
+/// them best first from the point of view of the player to move. Equal scores keep the order
+/// of `legal_moves()`. Each action is searched with a full window, so the scores are exact.
+pub fn rank_moves(game: &Game, depth: u32) -> Vec<(Action, i32)> {
+    let mut game = game.clone();
+    let player = game.to_move();
+    let mut ranked: Vec<(Action, i32)> = game
+        .legal_moves()
+        .into_iter()
+        .map(|action| {
+            let score = score_action(
+                &mut game,
+                action,
+                player,
+                depth.saturating_sub(1),
+                -WIN_SCORE * 2,
+                WIN_SCORE * 2,
+            );
+            (action, score)
+        })
+        .collect();
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked
+}
+
+/// Picks one of the legal actions uniformly at random.
+/// The caller provides the random number generator, so a seeded one gives reproducible games.
+/// Returns `None` if the game is over or there is nothing to do.
+#[cfg(feature = "rand")]
+pub fn random_action(game: &Game, rng: &mut impl rand::RngCore) -> Option<Action> {
+    use rand::Rng;
+
+    if game.result() != GameResult::Ongoing {
+        return None;
+    }
+    let moves = game.legal_moves();
+    if moves.is_empty() {
+        return None;
+    }
+    Some(moves[rng.random_range(0..moves.len())])
+}
+
+/// Plays `game` to the end, asking `white` and `black` for the actions of their side,
+/// including the removals after their mills. Returns the result of the game.
+///
+/// An action that `game` rejects forfeits the game for the player who chose it, see
+/// `Game::resign`. After `max_plies` actions without a result the game counts as a draw
+/// without progress, even though `game` itself is still ongoing.
+pub fn play_out(
+    game: &mut Game,
+    mut white: impl FnMut(&Game) -> Action,
+    mut black: impl FnMut(&Game) -> Action,
+    max_plies: usize,
+) -> GameResult {
+    for _ in 0..max_plies {
+        if game.is_game_over() {
+            break;
+        }
+        let player = game.to_move();
+        let action = match player {
+            Color::White => white(game),
+            Color::Black => black(game),
+        };
+        if game.action(action).is_err() {
+            game.resign(player);
+        }
+    }
+    match game.result() {
+        GameResult::Ongoing => GameResult::Draw(DrawReason::NoProgress),
+        result => result,
+    }
+}
+
+/// Scores the position from the point of view of `perspective`.
+/// Positive values are good for `perspective`.
+///
+/// The score combines material (pieces on the board and still to place),
+/// closed mills, mobility (empty points next to own pieces) and
+/// the positional weights of the occupied points, see `Game::positional_score`.
+pub fn evaluate(game: &Game, perspective: Player) -> i32 {
+    let score = |color: Color| {
+        let points = game.points();
+        let pieces = game.count_pieces(color) as i32 + game.remaining_unplaced(color) as i32;
+        let mills = game.count_mills(color) as i32;
+        let mobility = game
+            .pieces_of(color)
+            .flat_map(|p| game.variant().neighbors(p))
+            .filter(|&n| points[n].is_none())
+            .count() as i32;
+        let position = game.positional_score(color);
+        100 * pieces + 30 * mills + 5 * mobility + 2 * position
+    };
+    score(perspective) - score(perspective.opposite())
+}
+
+// Applies `action` for `player`, scores the result from `player`'s point of view and undoes it.
+fn score_action(
+    game: &mut Game,
+    action: Action,
+    player: Player,
+    depth: u32,
+    alpha: i32,
+    beta: i32,
+) -> i32 {
+    game.action(action).expect("generated action is legal");
+    // after closing a mill the same player removes a piece, so the score keeps its sign
+    let score = if game.to_move() == player {
+        negamax(game, depth, alpha, beta)
+    } else {
+        -negamax(game, depth, -beta, -alpha)
+    };
+    game.undo().expect("action was just applied");
+    score
+}
+
+fn negamax(game: &mut Game, depth: u32, mut alpha: i32, beta: i32) -> i32 {
+    let player = game.to_move();
+    match game.result() {
+        // prefer quick wins and slow losses
+        GameResult::Win(winner) if winner == player => return WIN_SCORE + depth as i32,
+        GameResult::Win(_) => return -WIN_SCORE - depth as i32,
+        GameResult::Draw(_) => return 0,
+        GameResult::Ongoing => {}
+    }
+    let moves = game.ordered_moves();
+    if depth == 0 || moves.is_empty() {
+        return evaluate(game, player);
+    }
+
+    let mut best = -WIN_SCORE * 2;
+    for action in moves {
+        let score = score_action(game, action, player, depth - 1, alpha, beta);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmm::{ActionKind, GameBuilder, GameConfig};
+
+    #[test]
+    fn test_evaluate_is_symmetric() {
+        let game: Game = "WW.WBB.....B........W.B.".parse().unwrap();
+        assert_eq!(
+            evaluate(&game, Player::White),
+            -evaluate(&game, Player::Black)
+        );
+        assert_eq!(evaluate(&Game::new(), Player::White), 0);
+    }
+
+    #[test]
+    fn test_evaluate_prefers_cross_points() {
+        let mut cross = Game::new();
+        cross.action("W P 9".parse().unwrap()).unwrap();
+        let mut corner = Game::new();
+        corner.action("W P 8".parse().unwrap()).unwrap();
+        assert!(evaluate(&cross, Player::White) > evaluate(&corner, Player::White));
+    }
+
+    #[test]
+    fn test_best_action_closes_mill_and_removes() {
+        let mut game: Game = "WW.WBB.....B........W.B.".parse().unwrap();
+        let action = best_action(&game, 2).unwrap();
+        assert_eq!(action, "W M 3 2".parse().unwrap());
+
+        game.action(action).unwrap();
+        let removal = best_action(&game, 2).unwrap();
+        assert!(matches!(removal.action, ActionKind::Remove(_)));
+        assert!(game.is_legal(removal));
+    }
+
+    #[test]
+    fn test_ai_action() {
+        // Easy may play at random, but only legal actions
+        let mut game = Game::new();
+        for _ in 0..30 {
+            // random play can end the game early
+            let Some(action) = ai_action(&game, AiLevel::Easy) else {
+                assert!(game.is_game_over());
+                break;
+            };
+            game.action(action).unwrap();
+        }
+
+        // closing 6-7-0 takes Black's seventh piece
+        let game = GameBuilder::new()
+            .place(0, Color::White)
+            .place(6, Color::White)
+            .place(15, Color::White)
+            .place(20, Color::White)
+            .place(3, Color::Black)
+            .place(10, Color::Black)
+            .place(12, Color::Black)
+            .removed(Color::White, 5)
+            .removed(Color::Black, 6)
+            .build()
+            .unwrap();
+        assert_eq!(
+            ai_action(&game, AiLevel::Hard),
+            Some("W M 15 7".parse().unwrap())
+        );
+
+        let won: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert_eq!(ai_action(&won, AiLevel::Medium), None);
+    }
+
+    #[test]
+    fn test_distance_to_loss() {
+        // Black cannot reach 7, so White closes 6-7-0 and takes Black's third piece
+        let config = GameConfig {
+            flying_enabled: false,
+            ..GameConfig::default()
+        };
+        let builder = GameBuilder::new()
+            .config(config)
+            .place(0, Color::White)
+            .place(6, Color::White)
+            .place(15, Color::White)
+            .place(20, Color::White)
+            .place(3, Color::Black)
+            .place(10, Color::Black)
+            .place(22, Color::Black)
+            .removed(Color::White, 5)
+            .removed(Color::Black, 6);
+        let lost = builder.clone().to_move(Player::Black).build().unwrap();
+        assert_eq!(distance_to_loss(&lost, 4), Some(3));
+        assert_eq!(distance_to_loss(&lost, 2), None);
+
+        let winning = builder.build().unwrap();
+        assert_eq!(distance_to_loss(&winning, 4), None);
+
+        let over: Game = "WW.B.B.B..............B. W".parse().unwrap();
+        assert_eq!(distance_to_loss(&over, 4), Some(0));
+    }
+
+    #[test]
+    fn test_rank_moves() {
+        let game: Game = "WW.WBB.....B........W.B.".parse().unwrap();
+        let ranked = rank_moves(&game, 2);
+        assert_eq!(ranked.len(), game.legal_moves().len());
+        // closing the mill 0-1-2 wins a piece
+        assert_eq!(ranked[0].0, "W M 3 2".parse().unwrap());
+        assert!(ranked[0].1 > ranked[1].1);
+        assert!(ranked.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(ranked[0].0, best_action(&game, 2).unwrap());
+
+        let over: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert!(rank_moves(&over, 2).is_empty());
+    }
+
+    #[test]
+    fn test_best_action_game_over() {
+        let game: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert_eq!(best_action(&game, 3), None);
+    }
+
+    #[test]
+    fn test_best_action_timed() {
+        let game: Game = "WW.WBB.....B........W.B.".parse().unwrap();
+        let action = best_action_timed(&game, Duration::ZERO).unwrap();
+        assert!(game.is_legal(action));
+        let action = best_action_timed(&game, Duration::from_millis(50)).unwrap();
+        assert!(game.is_legal(action));
+
+        let over: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert_eq!(best_action_timed(&over, Duration::from_millis(50)), None);
+    }
+
+    #[test]
+    fn test_play_out() {
+        let first = |game: &Game| game.legal_moves()[0];
+        let mut game = Game::new();
+        assert_eq!(
+            play_out(&mut game, first, first, 4),
+            GameResult::Draw(DrawReason::NoProgress)
+        );
+        assert_eq!(game.move_log().len(), 4);
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        // Black answers with an occupied point and forfeits
+        let occupied = |_: &Game| "B P 0".parse().unwrap();
+        let mut game = Game::new();
+        assert_eq!(
+            play_out(&mut game, first, occupied, 100),
+            GameResult::Win(Player::White)
+        );
+        assert_eq!(game.move_log().len(), 1);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_play_out_random_bots() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let random_game = |seed: u64| {
+            let mut white_rng = StdRng::seed_from_u64(seed);
+            let mut black_rng = StdRng::seed_from_u64(seed + 1);
+            let mut game = Game::new();
+            let result = play_out(
+                &mut game,
+                |game| random_action(game, &mut white_rng).unwrap(),
+                |game| random_action(game, &mut black_rng).unwrap(),
+                1000,
+            );
+            (result, game.move_log().to_vec())
+        };
+        let (result, actions) = random_game(3);
+        assert_ne!(result, GameResult::Ongoing);
+        assert!(actions.len() >= 18);
+        assert_eq!(random_game(3), (result, actions));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_ai_action_with_rng_is_reproducible() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let easy_game = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut game = Game::new();
+            let mut random = 0;
+            for _ in 0..30 {
+                let action = ai_action_with_rng(&game, AiLevel::Easy, &mut rng).unwrap();
+                random += (Some(action) != best_action(&game, 1)) as usize;
+                game.action(action).unwrap();
+            }
+            (game.move_log().to_vec(), random)
+        };
+        let (actions, random) = easy_game(3);
+        assert_eq!(easy_game(3).0, actions);
+        // Easy mixes in random actions
+        assert!(random > 0);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random_action_is_reproducible() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let random_game = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut game = Game::new();
+            let mut actions = Vec::new();
+            while let Some(action) = random_action(&game, &mut rng) {
+                game.action(action).unwrap();
+                actions.push(action);
+                if actions.len() == 60 {
+                    break;
+                }
+            }
+            actions
+        };
+        let actions = random_game(7);
+        assert!(actions.len() > 20);
+        assert_eq!(random_game(7), actions);
+        assert_ne!(random_game(8), actions);
+    }
+}
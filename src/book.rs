@@ -0,0 +1,203 @@
+// Opening book for Nine Men's Morris
+// Positions are looked up by `Game::canonical_hash`, so one entry covers all symmetric positions.
+// Actions are stored as they apply to the canonical board and mapped back on lookup.
+//
+// Text format, one position per line:
+//   <canonical hash in hex>: <action>, <action>, ...
+// e.g. `1a2b3c4d5e6f7a8b: W P 1, W P 9`. Empty lines and lines starting with `#` are skipped.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::nmm::{Action, ActionKind, Game, NmmGame, Point, Variant};
+
+/// Recommended actions per position. The default book is empty.
+#[derive(Clone, Default, Debug)]
+pub struct Book {
+    entries: HashMap<u64, Vec<Action>>,
+}
+
+impl Book {
+    /// Creates an empty book.
+    pub fn new() -> Self {
+        Book::default()
+    }
+
+    /// Returns the number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the book has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Recommends `action` in the position of `game`, and in all positions symmetric to it.
+    /// Fails if the action is not legal there or the game is not standard Nine Men's Morris.
+    pub fn insert(&mut self, game: &Game, action: Action) -> Result<(), &'static str> {
+        if game.variant() != Variant::Nine {
+            return Err("Book only supports Nine Men's Morris");
+        }
+        if !game.is_legal(action) {
+            return Err("Illegal book move");
+        }
+        let to_canonical = canonical_symmetry(game);
+        let action = map_action(action, &to_canonical);
+        let actions = self.entries.entry(game.canonical_hash()).or_default();
+        if !actions.contains(&action) {
+            actions.push(action);
+        }
+        Ok(())
+    }
+
+    /// Returns the recommended actions for the position of `game`, in insertion order.
+    pub fn moves(&self, game: &Game) -> Vec<Action> {
+        if game.variant() != Variant::Nine {
+            return Vec::new();
+        }
+        let Some(actions) = self.entries.get(&game.canonical_hash()) else {
+            return Vec::new();
+        };
+        let to_canonical = canonical_symmetry(game);
+        let mut from_canonical = [0; 24];
+        for (p, &image) in to_canonical.iter().enumerate() {
+            from_canonical[image] = p;
+        }
+        actions
+            .iter()
+            .map(|&action| map_action(action, &from_canonical))
+            .collect()
+    }
+
+    /// Returns the first recommended action for the position of `game`, if any.
+    pub fn book_move(&self, game: &Game) -> Option<Action> {
+        self.moves(game).into_iter().next()
+    }
+}
+
+// a transform from `Game::SYMMETRIES` that turns the board of `game` into its canonical board
+fn canonical_symmetry(game: &Game) -> [Point; 24] {
+    let canonical = game.canonical();
+    Game::SYMMETRIES
+        .into_iter()
+        .find(|sym| {
+            let mut board = [None; 24];
+            for (p, &piece) in game.points().iter().enumerate() {
+                board[sym[p]] = piece;
+            }
+            board == canonical
+        })
+        .expect("the canonical board is one of the transforms")
+}
+
+fn map_action(action: Action, permutation: &[Point; 24]) -> Action {
+    let kind = match action.action {
+        ActionKind::Place(p) => ActionKind::Place(permutation[p]),
+        ActionKind::Move(from, to) => ActionKind::Move(permutation[from], permutation[to]),
+        ActionKind::Remove(p) => ActionKind::Remove(permutation[p]),
+    };
+    Action {
+        player: action.player,
+        action: kind,
+    }
+}
+
+impl FromStr for Book {
+    type Err = &'static str;
+
+    /// Parses the text format described at the top of this module.
+    /// The actions are taken as they are, i.e. for the canonical board.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut book = Book::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (hash, actions) = line.split_once(':').ok_or("Invalid book line")?;
+            let hash = u64::from_str_radix(hash.trim(), 16).map_err(|_| "Invalid book hash")?;
+            let actions = actions
+                .split(',')
+                .map(|action| action.parse())
+                .collect::<Result<Vec<Action>, _>>()?;
+            book.entries.entry(hash).or_default().extend(actions);
+        }
+        Ok(book)
+    }
+}
+
+impl Display for Book {
+    /// Writes the book in the text format, ordered by hash.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut hashes: Vec<&u64> = self.entries.keys().collect();
+        hashes.sort();
+        for hash in hashes {
+            let actions: Vec<String> = self.entries[hash].iter().map(|a| a.to_string()).collect();
+            writeln!(f, "{hash:016x}: {}", actions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_move_opening() {
+        let mut book = Book::new();
+        let game = Game::new();
+        assert!(book.is_empty());
+        assert_eq!(book.book_move(&game), None);
+
+        let action: Action = "W P 9".parse().unwrap();
+        book.insert(&game, action).unwrap();
+        assert_eq!(book.len(), 1);
+        let recommended = book.book_move(&game).unwrap();
+        assert!(game.is_legal(recommended));
+        // the empty board is symmetric, so any middle point of the middle square is the same move
+        assert!(matches!(
+            recommended.action,
+            ActionKind::Place(9 | 11 | 13 | 15)
+        ));
+
+        assert!(book.insert(&game, "B P 0".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_book_maps_symmetric_positions() {
+        let mut a = Game::new();
+        a.action("W P 0".parse().unwrap()).unwrap();
+        let mut book = Book::new();
+        book.insert(&a, "B P 1".parse().unwrap()).unwrap();
+
+        // the same position rotated by a quarter turn
+        let mut b = Game::new();
+        b.action("W P 2".parse().unwrap()).unwrap();
+        let recommended = book.moves(&b);
+        assert_eq!(recommended.len(), 1);
+        // next to the white corner, on either side
+        assert!(matches!(recommended[0].action, ActionKind::Place(1 | 3)));
+    }
+
+    #[test]
+    fn test_book_text_round_trip() {
+        let mut book = Book::new();
+        let mut game = Game::new();
+        book.insert(&game, "W P 9".parse().unwrap()).unwrap();
+        game.action("W P 9".parse().unwrap()).unwrap();
+        book.insert(&game, "B P 17".parse().unwrap()).unwrap();
+        book.insert(&game, "B P 8".parse().unwrap()).unwrap();
+
+        let text = book.to_string();
+        let parsed: Book = text.parse().unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.moves(&game), book.moves(&game));
+
+        assert!("# comment\n\n".parse::<Book>().unwrap().is_empty());
+        assert_eq!("zz: W P 0".parse::<Book>().err(), Some("Invalid book hash"));
+        assert_eq!("W P 0".parse::<Book>().err(), Some("Invalid book line"));
+    }
+}
@@ -1 +1,5 @@
+pub mod ai;
+pub mod book;
 pub mod nmm;
+pub mod protocol;
+pub mod tablebase;
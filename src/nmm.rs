@@ -3,9 +3,14 @@
 // Rules (incl. 'flying'): https://en.wikipedia.org/wiki/Nine_men%27s_morris
 // White begins
 
-use std::{fmt::Display, str::FromStr};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    str::FromStr,
+};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     Black,
     White,
@@ -20,6 +25,29 @@ impl Color {
     }
 }
 
+impl FromStr for Color {
+    type Err = &'static str;
+
+    /// Accepts `W`, `White`, `B` and `Black`, in any case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "w" | "white" => Ok(Color::White),
+            "b" | "black" => Ok(Color::Black),
+            _ => Err("Invalid player"),
+        }
+    }
+}
+
+impl Display for Color {
+    /// Writes `W` or `B`, as used in actions.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Color::White => write!(f, "W"),
+            Color::Black => write!(f, "B"),
+        }
+    }
+}
+
 pub type Player = Color;
 pub type Piece = Color;
 /// The board is represented by 24 points, numbered as follows:
@@ -32,20 +60,245 @@ pub type Piece = Color;
 /// 6––––––––5 –––––––4
 pub type Point = usize; // 0–23
 
-/// Describes the contents of an action.
+/// The phase a single player is in. Phases are tracked per player, not per game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    /// The player still has unplaced pieces.
+    Placement,
+    /// All pieces are placed and the player has more than three on the board.
+    Movement,
+    /// All pieces are placed and exactly three remain, so any empty point can be reached.
+    Flying,
+}
+
+/// The state of the game as a whole.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    Ongoing,
+    Win(Player),
+    Draw(DrawReason),
+}
+
+/// Why a game ended in a draw.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+    /// The same position occurred for the third time.
+    Repetition,
+    /// Too many plies were played without a mill or a removal, see `Game::NO_PROGRESS_LIMIT`.
+    NoProgress,
+    /// The side to move had no legal move and the rules score this as a draw.
+    Stalemate,
+}
+
+/// Why a game was won.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WinReason {
+    /// The loser had too many pieces removed (seven in the standard game).
+    SevenRemoved,
+    /// The loser had fewer than three pieces left and none to place.
+    TooFewPieces,
+    /// The loser could not make a legal move.
+    NoLegalMoves,
+    /// The loser resigned or lost on time, see `Game::resign`.
+    Resignation,
+}
+
+/// What happened while an action was applied, as reported by `Game::action_events`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    Placed {
+        player: Player,
+        point: Point,
+    },
+    Moved {
+        player: Player,
+        from: Point,
+        to: Point,
+    },
+    /// The piece just placed or moved closed `mill`; a removal follows.
+    MillFormed {
+        player: Player,
+        mill: [Point; 3],
+    },
+    /// The piece just placed or moved closed `mill`, but no piece can be removed, see
+    /// `Game::mill_without_removal`; the turn passes instead.
+    MillWithoutRemoval {
+        player: Player,
+        mill: [Point; 3],
+    },
+    Removed {
+        player: Player,
+        point: Point,
+    },
+    /// It is now `player`'s turn.
+    TurnPassed {
+        player: Player,
+    },
+}
+
+/// One point of the board as seen by a renderer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Square {
+    pub point: Point,
+    pub piece: Option<Piece>,
+    /// Whether the piece on this point is part of a closed mill.
+    pub in_mill: bool,
+}
+
+/// Why `action()`, `undo()` or `redo()` failed. `Display` gives a short English message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActionError {
+    /// A point of the action is not on the board of the variant.
+    PointOutOfRange,
+    /// The action is not by the side to move.
+    NotYourTurn,
+    /// The other player has closed a mill and must remove a piece first.
+    RemovalPending,
+    /// The player has closed a mill and must remove a piece before doing anything else.
+    MustRemove,
+    /// A removal targets an empty point or a piece of the removing player.
+    NotOpponentPiece,
+    /// A removal targets a piece in a mill, which the mill removal policy does not allow.
+    PieceInMill,
+    /// A removal without a closed mill.
+    RemoveNotAllowed,
+    /// A placement after all pieces were placed.
+    NoPiecesToPlace,
+    /// A placement on an occupied point.
+    Occupied,
+    /// A move before all pieces were placed.
+    StillPlacing,
+    /// A move from a point without a piece of the player.
+    NoPieceAtSource,
+    /// A move to an occupied point.
+    DestinationOccupied,
+    /// A move to the point it starts from.
+    SamePoint,
+    /// A move along no line while the player may not fly.
+    NotAdjacent,
+    /// A move that closes the mill the player's previous move opened, while the rules
+    /// forbid that (`GameConfig::forbid_immediate_remill`).
+    ImmediateRemill,
+    /// An action after a player resigned, see `Game::resign`.
+    GameResigned,
+    /// `undo()` without an action or resignation to take back.
+    NothingToUndo,
+    /// `redo()` without an undone action to reapply.
+    NothingToRedo,
+}
+
+impl ActionError {
+    /// The message of the error, as shown by `Display`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActionError::PointOutOfRange => "Point out of range",
+            ActionError::NotYourTurn => "Not this player's turn",
+            ActionError::RemovalPending => "This player must remove",
+            ActionError::MustRemove => "Must remove a piece",
+            ActionError::NotOpponentPiece => "Can only remove opponent piece",
+            ActionError::PieceInMill => "Cannot remove a piece in a mill",
+            ActionError::RemoveNotAllowed => "Remove not allowed now",
+            ActionError::NoPiecesToPlace => "No pieces left to place",
+            ActionError::Occupied => "Point already occupied",
+            ActionError::StillPlacing => "Must place all pieces before moving",
+            ActionError::NoPieceAtSource => "No piece of this player at source",
+            ActionError::DestinationOccupied => "Destination not empty",
+            ActionError::SamePoint => "Source and destination must differ",
+            ActionError::NotAdjacent => "Points not adjacent",
+            ActionError::ImmediateRemill => "Cannot close the mill just opened",
+            ActionError::GameResigned => "Game ended by resignation",
+            ActionError::NothingToUndo => "No action to undo",
+            ActionError::NothingToRedo => "No action to redo",
+        }
+    }
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// Why a game or action could not be read from text or bytes, e.g. by `Game::replay`,
+/// `Game::from_record` or `Game::from_bytes`. `Display` gives a short English message.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadError {
+    /// The input is malformed, e.g. an unknown action or truncated data.
+    Parse(&'static str),
+    /// The input describes a position that cannot occur, see `Game::validate`.
+    Position(&'static str),
+    /// An action of the input cannot be applied to the game.
+    Action(ActionError),
+    /// The result of a record differs from the result of its finished game.
+    ResultMismatch,
+}
+
+impl LoadError {
+    /// The message of the error, as shown by `Display`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LoadError::Parse(message) | LoadError::Position(message) => message,
+            LoadError::Action(err) => err.as_str(),
+            LoadError::ResultMismatch => "Record result does not match the game",
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<ActionError> for LoadError {
+    fn from(err: ActionError) -> Self {
+        LoadError::Action(err)
+    }
+}
+
+/// Describes the contents of an action.
+/// Kinds are ordered placements first, then moves, then removals, and by their points within
+/// a kind (`from` before `to`).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ActionKind {
     Place(Point),
     Move(Point, Point),
     Remove(Point),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// Actions are ordered by player (Black first, as in `Color`) and then by kind.
+/// This order is stable and may be relied on, e.g. for sorted move lists.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Action {
     pub player: Player,
     pub action: ActionKind,
 }
 
+// the current UTC date as `YYYY-MM-DD`
+fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    // days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
 // This implementation is used extensively for testing
 impl FromStr for Action {
     type Err = &'static str;
@@ -59,11 +312,7 @@ impl FromStr for Action {
         if parts.len() < 3 {
             return Err("Invalid action format");
         }
-        let player = match parts[0] {
-            "W" => Player::White,
-            "B" => Player::Black,
-            _ => return Err("Invalid player"),
-        };
+        let player: Player = parts[0].parse()?;
         let action = match parts[1] {
             "P" => {
                 let point: Point = parts[2].parse().map_err(|_| "Invalid point")?;
@@ -89,27 +338,46 @@ impl FromStr for Action {
 
 impl Display for Action {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let player_str = match self.player {
-            Player::White => "W",
-            Player::Black => "B",
-        };
         let action_str = match self.action {
             ActionKind::Place(p) => format!("P {p}"),
             ActionKind::Move(from, to) => format!("M {from} {to}"),
             ActionKind::Remove(p) => format!("R {p}"),
         };
-        write!(f, "{player_str} {action_str}")
+        write!(f, "{} {action_str}", self.player)
     }
 }
 
+/// The algebraic coordinate of every point, indexed by point.
+/// Files `a`–`g` run from left to right and ranks `1`–`7` from bottom to top,
+/// so the outer square spans `a1`–`g7` and the inner square `c3`–`e5`.
+pub const COORDINATES: [&str; 24] = [
+    "a7", "d7", "g7", "g4", "g1", "d1", "a1", "a4", // outer square
+    "b6", "d6", "f6", "f4", "f2", "d2", "b2", "b4", // middle square
+    "c5", "d5", "e5", "e4", "e3", "d3", "c3", "c4", // inner square
+];
+
+/// Returns the algebraic coordinate of `point`, e.g. `"a7"` for point 0.
+/// Panics if `point` is out of range.
+pub fn point_to_coord(point: Point) -> String {
+    COORDINATES[point].to_string()
+}
+
+/// Returns the point with the algebraic coordinate `coord` (case-insensitive),
+/// or `None` if no point has that coordinate.
+pub fn coord_to_point(coord: &str) -> Option<Point> {
+    COORDINATES
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(coord.trim()))
+}
+
 pub trait NmmGame {
     /// Creates a new instance with an empty board.
     fn new() -> Self;
     /// Applies the given action.
-    fn action(&mut self, action: Action) -> Result<(), &'static str>;
+    fn action(&mut self, action: Action) -> Result<(), ActionError>;
     /// Undoes the last action.
     /// This should fail if there is no last action to be undone.
-    fn undo(&mut self) -> Result<(), &'static str>;
+    fn undo(&mut self) -> Result<(), ActionError>;
     /// All poinst of the game board
     fn points(&self) -> &[Option<Piece>; 24];
     /// Returns if there is currently a winner.
@@ -125,29 +393,573 @@ the trait should be implemented.
 */
 
 
+/// The supported Morris variants. All of them number their points like the standard board:
+/// - `Three`: the outer square (0–7) and its center (8), including the diagonals.
+///   Three pieces each and no flying, so closing a mill decides the game.
+/// - `Six`: the outer and middle squares (0–15), six pieces each and no flying.
+/// - `Nine`: the standard game.
+/// - `Twelve`: the standard board plus diagonal lines through the corners, twelve pieces each.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Variant {
+    Three,
+    Six,
+    Nine,
+    Twelve,
+}
+
+impl Variant {
+    /// Number of pieces each player starts with.
+    pub fn pieces(self) -> u8 {
+        match self {
+            Variant::Three => 3,
+            Variant::Six => 6,
+            Variant::Nine => 9,
+            Variant::Twelve => 12,
+        }
+    }
+
+    /// Number of points on the board. Valid points are `0..point_count()`.
+    pub fn point_count(self) -> usize {
+        self.topology().point_count
+    }
+
+    /// Returns true if a player with three pieces left may move to any empty point.
+    pub fn flying(self) -> bool {
+        matches!(self, Variant::Nine | Variant::Twelve)
+    }
+
+    /// The points, lines and mills of the variant's board.
+    pub fn topology(self) -> &'static Topology {
+        match self {
+            Variant::Three => &THREE_TOPOLOGY,
+            Variant::Six => &SIX_TOPOLOGY,
+            Variant::Nine => &NINE_TOPOLOGY,
+            Variant::Twelve => &TWELVE_TOPOLOGY,
+        }
+    }
+
+    /// All mills of the variant.
+    pub fn mills(self) -> &'static [[Point; 3]] {
+        self.topology().mills
+    }
+
+    // bitmasks of the mills, in the order of `mills()`
+    fn mill_masks(self) -> &'static [u32] {
+        self.topology().mill_masks
+    }
+
+    /// Returns true if `from` and `to` are connected by a line.
+    pub fn are_adjacent(self, from: Point, to: Point) -> bool {
+        self.topology().are_adjacent(from, to)
+    }
+
+    /// Returns the points adjacent to `point`, or an empty list if `point` is out of range.
+    pub fn neighbors(self, point: Point) -> Vec<Point> {
+        self.topology().neighbors(point)
+    }
+
+    fn adjacency(self) -> &'static [u32; 24] {
+        &self.topology().adjacency
+    }
+}
+
+/// The structure of a board: its points, the lines between them and its mills.
+/// All boards number their points like the standard board, see `Variant`.
+#[derive(Debug)]
+pub struct Topology {
+    point_count: usize,
+    mills: &'static [[Point; 3]],
+    // bitmasks of the mills, in the order of `mills`
+    mill_masks: &'static [u32],
+    // bit `n` of entry `p` is set if `p` and `n` are connected by a line
+    adjacency: [u32; 24],
+}
+
+impl Topology {
+    /// Number of points. Valid points are `0..point_count()`.
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// All mills, i.e. lines of three points that close a mill.
+    pub fn mills(&self) -> &'static [[Point; 3]] {
+        self.mills
+    }
+
+    /// Returns true if `from` and `to` are connected by a line.
+    pub fn are_adjacent(&self, from: Point, to: Point) -> bool {
+        from < 24 && to < 24 && self.adjacency[from] & (1 << to) != 0
+    }
+
+    /// Returns the points adjacent to `point`, or an empty list if `point` is out of range.
+    pub fn neighbors(&self, point: Point) -> Vec<Point> {
+        (0..24).filter(|&n| self.are_adjacent(point, n)).collect()
+    }
+
+    /// Returns the indices into `mills()` of the mills through `point`, in table order.
+    pub fn point_mills(&self, point: Point) -> Vec<usize> {
+        (0..self.mills.len())
+            .filter(|&i| self.mills[i].contains(&point))
+            .collect()
+    }
+}
+
+/// Which pieces in mills may be removed after closing a mill.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MillRemovalPolicy {
+    /// Pieces in mills may only be removed if all of the opponent's pieces are in mills.
+    #[default]
+    Standard,
+    /// Any opponent piece may be removed, in a mill or not.
+    AlwaysAllowed,
+    /// Pieces in mills are never removed. If all opponent pieces are in mills, the mill
+    /// removes nothing and the turn passes.
+    NeverFromMill,
+}
+
+/// Rule settings of a game. The default is standard Nine Men's Morris.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    /// The board and the number of pieces per player.
+    pub variant: Variant,
+    /// Whether a player with three pieces left may move to any empty point.
+    pub flying_enabled: bool,
+    /// A player loses once this many of their pieces were removed.
+    pub loss_threshold: u8,
+    /// Whether a player who cannot move draws instead of losing.
+    pub stalemate_is_draw: bool,
+    /// The player who places the first piece.
+    pub first_player: Player,
+    /// Which pieces in mills may be removed.
+    pub mill_removal_policy: MillRemovalPolicy,
+    /// Whether a player may not close a mill that their previous move opened, i.e. move a
+    /// piece out of a mill and straight back in to remove a piece every other turn.
+    pub forbid_immediate_remill: bool,
+}
+
+impl GameConfig {
+    /// The usual rules of `variant`.
+    pub fn for_variant(variant: Variant) -> Self {
+        GameConfig {
+            variant,
+            flying_enabled: variant.flying(),
+            loss_threshold: variant.pieces() - 2,
+            stalemate_is_draw: false,
+            first_player: Player::White,
+            mill_removal_policy: MillRemovalPolicy::Standard,
+            forbid_immediate_remill: false,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::for_variant(Variant::Nine)
+    }
+}
+
+const THREE_MILLS: [[Point; 3]; 8] = [
+    [0, 1, 2],
+    [2, 3, 4],
+    [4, 5, 6],
+    [6, 7, 0],
+    [1, 8, 5],
+    [7, 8, 3],
+    [0, 8, 4],
+    [2, 8, 6],
+];
+
+const TWELVE_MILLS: [[Point; 3]; 20] = {
+    let mut mills = [[0; 3]; 20];
+    let mut i = 0;
+    while i < 16 {
+        mills[i] = Game::MILLS[i];
+        i += 1;
+    }
+    // diagonals through the corners
+    while i < 20 {
+        let corner = 2 * (i - 16);
+        mills[i] = [corner, corner + 8, corner + 16];
+        i += 1;
+    }
+    mills
+};
+
+static THREE_TOPOLOGY: Topology = Topology {
+    point_count: 9,
+    mills: &THREE_MILLS,
+    mill_masks: &THREE_MILL_MASKS,
+    adjacency: THREE_ADJACENCY,
+};
+static SIX_TOPOLOGY: Topology = Topology {
+    point_count: 16,
+    mills: NINE_MILLS.split_at(8).0,
+    mill_masks: NINE_MILL_MASKS.split_at(8).0,
+    adjacency: SIX_ADJACENCY,
+};
+static NINE_TOPOLOGY: Topology = Topology {
+    point_count: 24,
+    mills: &NINE_MILLS,
+    mill_masks: &NINE_MILL_MASKS,
+    adjacency: NINE_ADJACENCY,
+};
+static TWELVE_TOPOLOGY: Topology = Topology {
+    point_count: 24,
+    mills: &TWELVE_MILLS,
+    mill_masks: &TWELVE_MILL_MASKS,
+    adjacency: TWELVE_ADJACENCY,
+};
+
+// statics rather than consts, so Six Men's Morris can borrow their first eight mills
+static NINE_MILLS: [[Point; 3]; 16] = Game::MILLS;
+
+const THREE_MILL_MASKS: [u32; 8] = mill_masks(&THREE_MILLS);
+static NINE_MILL_MASKS: [u32; 16] = mill_masks(&Game::MILLS);
+const TWELVE_MILL_MASKS: [u32; 20] = mill_masks(&TWELVE_MILLS);
+
+const fn mill_masks<const N: usize>(mills: &[[Point; 3]; N]) -> [u32; N] {
+    let mut masks = [0; N];
+    let mut i = 0;
+    while i < N {
+        masks[i] = 1 << mills[i][0] | 1 << mills[i][1] | 1 << mills[i][2];
+        i += 1;
+    }
+    masks
+}
+
+const THREE_ADJACENCY: [u32; 24] = adjacency(
+    8,
+    &[
+        (8, 0),
+        (8, 1),
+        (8, 2),
+        (8, 3),
+        (8, 4),
+        (8, 5),
+        (8, 6),
+        (8, 7),
+    ],
+);
+const SIX_ADJACENCY: [u32; 24] = adjacency(16, &[]);
+const NINE_ADJACENCY: [u32; 24] = adjacency(24, &[]);
+const TWELVE_ADJACENCY: [u32; 24] = adjacency(
+    24,
+    &[
+        (0, 8),
+        (8, 16),
+        (2, 10),
+        (10, 18),
+        (4, 12),
+        (12, 20),
+        (6, 14),
+        (14, 22),
+    ],
+);
+
+// Adjacency bitmasks: the lines of the standard board between points below `limit`,
+// plus the `extra` lines.
+const fn adjacency(limit: usize, extra: &[(Point, Point)]) -> [u32; 24] {
+    let mut masks = [0; 24];
+    let mut p = 0;
+    while p < limit {
+        let mut k = 0;
+        while k < 4 {
+            let n = Game::NEIGHBORS[p][k];
+            if n < limit {
+                masks[p] |= 1 << n;
+            }
+            k += 1;
+        }
+        p += 1;
+    }
+    let mut e = 0;
+    while e < extra.len() {
+        let (a, b) = extra[e];
+        masks[a] |= 1 << b;
+        masks[b] |= 1 << a;
+        e += 1;
+    }
+    masks
+}
+
+// Zobrist keys, generated at compile time with splitmix64 so hashes are stable across runs.
+// Layout: 24 points × 2 colors, black to move, must_remove per color, unplaced count per color.
+const ZOBRIST_BLACK_TO_MOVE: usize = 48;
+const ZOBRIST_MUST_REMOVE: usize = 49;
+const ZOBRIST_UNPLACED: usize = 51;
+const ZOBRIST_UNPLACED_MAX: usize = 16;
+const ZOBRIST_KEYS: [u64; ZOBRIST_UNPLACED + 2 * ZOBRIST_UNPLACED_MAX] = {
+    let mut keys = [0; ZOBRIST_UNPLACED + 2 * ZOBRIST_UNPLACED_MAX];
+    let mut state: u64 = 0x4e4d_4d5f_5a4f_4252;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+};
+
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 struct Snapshot {
     board: [Option<Piece>; 24],
+    bits: [u32; 2],
+    mills: u32,
+    board_hash: u64,
     to_move: Player,
     unplaced: [u8; 2],
     removed: [u8; 2],
     must_remove: Option<Player>,
+    last_mill: Option<[Point; 3]>,
+    quiet_moves: u32,
+}
+
+impl Snapshot {
+    // Two snapshots describe the same position if everything that affects future play matches.
+    // `unplaced` and `removed` are included so that placement-phase positions never repeat.
+    fn same_position(&self, other: &Snapshot) -> bool {
+        self.board == other.board
+            && self.to_move == other.to_move
+            && self.unplaced == other.unplaced
+            && self.removed == other.removed
+            && self.must_remove == other.must_remove
+    }
+}
+
+/// The complete state of a game without its history, for moving positions between processes.
+/// See `Game::position` and `Game::from_position`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub board: [Option<Piece>; 24],
+    pub to_move: Player,
+    /// Pieces still to be placed, White first.
+    pub unplaced: [u8; 2],
+    /// Pieces removed from the board, White first.
+    pub removed: [u8; 2],
+    pub must_remove: Option<Player>,
+    pub last_mill: Option<[Point; 3]>,
+    pub quiet_moves: u32,
+    pub config: GameConfig,
+}
+
+/// The headers of a game record, see `Game::to_record`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    pub white: String,
+    pub black: String,
+    /// The date of the game, as `YYYY-MM-DD`.
+    pub date: String,
+    pub result: GameResult,
+}
+
+/// Sets up a position piece by piece instead of playing up to it, e.g. for endgame tests.
+///
+/// Starts from an empty board with the standard rules and White to move. Unless set with
+/// `unplaced`, the pieces still to be placed are whatever the board and `removed` leave over.
+/// `build` checks the result like `Game::from_position`.
+#[derive(Clone, Debug)]
+pub struct GameBuilder {
+    position: Position,
+    unplaced: [Option<u8>; 2],
+    off_board: bool,
+}
+
+impl GameBuilder {
+    /// Creates a builder for an empty board with the standard rules.
+    pub fn new() -> Self {
+        GameBuilder::default()
+    }
+
+    /// Plays by `config` instead of the standard rules.
+    pub fn config(mut self, config: GameConfig) -> Self {
+        self.position.config = config;
+        self
+    }
+
+    /// Plays `variant` with its usual rules.
+    pub fn variant(self, variant: Variant) -> Self {
+        self.config(GameConfig::for_variant(variant))
+    }
+
+    /// Puts a piece of `color` on `point`, replacing whatever stood there.
+    pub fn place(mut self, point: Point, color: Color) -> Self {
+        match self.position.board.get_mut(point) {
+            Some(slot) => *slot = Some(color),
+            None => self.off_board = true,
+        }
+        self
+    }
+
+    /// Sets the side to move.
+    pub fn to_move(mut self, player: Player) -> Self {
+        self.position.to_move = player;
+        self
+    }
+
+    /// Sets how many pieces of `color` have been removed from the board.
+    pub fn removed(mut self, color: Color, count: u8) -> Self {
+        self.position.removed[Game::color_idx(color)] = count;
+        self
+    }
+
+    /// Sets how many pieces of `color` are still to be placed.
+    pub fn unplaced(mut self, color: Color, count: u8) -> Self {
+        self.unplaced[Game::color_idx(color)] = Some(count);
+        self
+    }
+
+    /// Sets the player that has closed a mill and must remove a piece next.
+    /// That player must also be the side to move.
+    pub fn must_remove(mut self, player: Option<Player>) -> Self {
+        self.position.must_remove = player;
+        self
+    }
+
+    /// Creates the game, or returns the first inconsistency as described in `Game::validate`.
+    pub fn build(self) -> Result<Game, &'static str> {
+        if self.off_board {
+            return Err("Piece outside the board");
+        }
+        let mut position = self.position;
+        let pieces = position.config.variant.pieces();
+        for color in [Color::White, Color::Black] {
+            let idx = Game::color_idx(color);
+            let on_board = position.board.iter().filter(|&&p| p == Some(color)).count() as u8;
+            position.unplaced[idx] = match self.unplaced[idx] {
+                Some(count) => count,
+                None => pieces
+                    .checked_sub(on_board)
+                    .and_then(|left| left.checked_sub(position.removed[idx]))
+                    .ok_or("Piece counts do not add up")?,
+            };
+        }
+        Game::from_position(position)
+    }
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        GameBuilder {
+            position: Game::new().position(),
+            unplaced: [None; 2],
+            off_board: false,
+        }
+    }
+}
+
+/// Steps back and forth through a recorded game without touching the game it came from,
+/// e.g. for a review screen. The cursor counts the actions applied, from 0 at the start of
+/// the game to `len()` at its end. Like `Game::replay`, the game starts from the empty
+/// standard board.
+#[derive(Clone)]
+pub struct Replay {
+    game: Game,
+    actions: Vec<Action>,
+    ply: usize,
+}
+
+impl Replay {
+    /// Creates a replay of `moves`, e.g. from `Game::move_log`, with the cursor at the start.
+    /// On the first action that cannot be applied, returns its index and error.
+    pub fn new(moves: &[Action]) -> Result<Replay, (usize, ActionError)> {
+        Game::new().apply_all(moves)?;
+        Ok(Replay {
+            game: Game::new(),
+            actions: moves.to_vec(),
+            ply: 0,
+        })
+    }
+
+    /// Returns the number of actions in the replay.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns true if the replay has no actions.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Returns the position of the cursor, i.e. how many actions have been applied.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Applies the next action. Does nothing at the end of the game.
+    pub fn step_forward(&mut self) {
+        if let Some(&action) = self.actions.get(self.ply) {
+            self.game
+                .action(action)
+                .expect("actions were checked by new");
+            self.ply += 1;
+        }
+    }
+
+    /// Takes back the last applied action. Does nothing at the start of the game.
+    pub fn step_back(&mut self) {
+        if self.ply > 0 {
+            self.game.undo().expect("an action was applied");
+            self.ply -= 1;
+        }
+    }
+
+    /// Moves the cursor to just after `ply` actions, or to the end if there are fewer.
+    pub fn seek(&mut self, ply: usize) {
+        let ply = ply.min(self.actions.len());
+        while self.ply > ply {
+            self.step_back();
+        }
+        while self.ply < ply {
+            self.step_forward();
+        }
+    }
+
+    /// Returns the game at the cursor.
+    pub fn current(&self) -> &Game {
+        &self.game
+    }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Game {
     board: [Option<Piece>; 24],
+    // one bitboard per color (White first), always in sync with `board`
+    bits: [u32; 2],
+    // bit `i` is set while mill `i` of the variant is closed, kept in sync by `set_point`
+    mills: u32,
+    // the Zobrist keys of the pieces on the board XOR-ed together, kept in sync by `set_point`
+    board_hash: u64,
     to_move: Player,
     unplaced: [u8; 2],
     removed: [u8; 2],
     must_remove: Option<Player>,
+    last_mill: Option<[Point; 3]>,
+    quiet_moves: u32,
+    config: GameConfig,
+    // the player who resigned; no action can follow, so this is never part of a snapshot
+    resigned: Option<Player>,
+    // the points of the removals by each player (White first), kept in step with `moves`
+    captures: [Vec<Point>; 2],
     history: Vec<Snapshot>,
+    // the applied actions, one for each snapshot in `history`
+    moves: Vec<Action>,
+    redo_stack: Vec<(Snapshot, Action)>,
 }
 
 impl Game {
     const INVALID: Point = 24;
 
-    // tüm olası değirmenler (20 adet)
-    const MILLS: [[Point; 3]; 16] = [
+    /// All 16 mills, i.e. lines of three points.
+    pub const MILLS: [[Point; 3]; 16] = [
         [0, 1, 2],
         [2, 3, 4],
         [4, 5, 6],
@@ -166,6 +978,42 @@ impl Game {
         [7, 15, 23],
     ];
 
+    /// For every point, the indices into `MILLS` of the two mills through it, in table order.
+    pub const POINT_MILLS: [[usize; 2]; 24] = {
+        let mut table = [[0; 2]; 24];
+        let mut found = [0; 24];
+        let mut i = 0;
+        while i < 16 {
+            let mut k = 0;
+            while k < 3 {
+                let p = Game::MILLS[i][k];
+                table[p][found[p]] = i;
+                found[p] += 1;
+                k += 1;
+            }
+            i += 1;
+        }
+        table
+    };
+
+    /// Positional weight of every point on the standard board: its number of neighbors,
+    /// so the cross points 9, 11, 13 and 15 are worth the most and the corners the least.
+    pub const POINT_VALUES: [i32; 24] = {
+        let mut table = [0; 24];
+        let mut p = 0;
+        while p < 24 {
+            let mut k = 0;
+            while k < 4 {
+                if Game::NEIGHBORS[p][k] != Game::INVALID {
+                    table[p] += 1;
+                }
+                k += 1;
+            }
+            p += 1;
+        }
+        table
+    };
+
     // her noktanın komşuları (max 4, fazlalar INVALID)
     const NEIGHBORS: [[Point; 4]; 24] = [
         [1, 7, Game::INVALID, Game::INVALID],     // 0
@@ -176,13 +1024,13 @@ impl Game {
         [4, 6, 13, Game::INVALID],    // 5
         [5, 7, Game::INVALID, Game::INVALID],    // 6
         [0, 6, 15, Game::INVALID],    // 7
-        [Game::INVALID, 9, 15, 16],               // 8
+        [9, 15, Game::INVALID, Game::INVALID],               // 8
         [1, 8, 10, 17],               // 9
-        [Game::INVALID, 9, 11, 18],               // 10
+        [9, 11, Game::INVALID, Game::INVALID],               // 10
         [3, 10, 12, 19],              // 11
-        [Game::INVALID, 11, 13, 20],              // 12
+        [11, 13, Game::INVALID, Game::INVALID],              // 12
         [5, 12, 14, 21],              // 13
-        [Game::INVALID, 13, 15, 22],              // 14
+        [13, 15, Game::INVALID, Game::INVALID],              // 14
         [7, 8, 14, 23],               // 15
         [Game::INVALID, 17, 23, Game::INVALID],   // 16
         [9, 16, 18, Game::INVALID],   // 17
@@ -194,304 +1042,4767 @@ impl Game {
         [15, 16, 22, Game::INVALID],  // 23
     ];
 
-    fn color_idx(c: Color) -> usize {
-        match c {
-            Color::White => 0,
-            Color::Black => 1,
+    /// Returns the points adjacent to `point` on the standard board,
+    /// or an empty list if `point` is out of range.
+    pub fn neighbors(point: Point) -> Vec<Point> {
+        match Self::NEIGHBORS.get(point) {
+            Some(neighbors) => neighbors.iter().copied().filter(|&n| n < 24).collect(),
+            None => Vec::new(),
         }
     }
 
-    fn snapshot(&self) -> Snapshot {
-        Snapshot {
-            board: self.board,
-            to_move: self.to_move,
-            unplaced: self.unplaced,
-            removed: self.removed,
-            must_remove: self.must_remove,
+    /// Returns the mills from `MILLS` that contain `point`, in table order.
+    /// Every point lies on exactly two mills; out-of-range points lie on none.
+    pub fn mills_containing(point: Point) -> Vec<[Point; 3]> {
+        match Self::POINT_MILLS.get(point) {
+            Some(mills) => mills.iter().map(|&i| Self::MILLS[i]).collect(),
+            None => Vec::new(),
         }
     }
 
-    fn forms_mill(&self, point: Point, color: Color) -> bool {
-        for mill in &Self::MILLS {
-            if mill.contains(&point) {
-                if self.board[mill[0]] == Some(color)
-                    && self.board[mill[1]] == Some(color)
-                    && self.board[mill[2]] == Some(color)
-                {
-                    return true;
-                }
+    /// Number of consecutive plies (moves by either player) without a mill after which the
+    /// game is drawn.
+    /// Placements, mills and removals reset the count.
+    pub const NO_PROGRESS_LIMIT: u32 = 50;
+
+    /// The 16 symmetries of the board as point permutations: `SYMMETRIES[i][p]` is the image of
+    /// point `p` under transform `i`. Transform `i` rotates by `i % 4` quarter turns clockwise,
+    /// after mirroring left to right if `i / 4 % 2 == 1` and swapping the inner and outer
+    /// squares if `i / 8 == 1`. `SYMMETRIES[0]` is the identity.
+    pub const SYMMETRIES: [[Point; 24]; 16] = {
+        let mut table = [[0; 24]; 16];
+        let mut i = 0;
+        while i < 16 {
+            let mut p = 0;
+            while p < 24 {
+                // each square is numbered clockwise from its top left corner
+                let (ring, pos) = (p / 8, p % 8);
+                let pos = if (i / 4) % 2 == 1 {
+                    (10 - pos) % 8
+                } else {
+                    pos
+                };
+                let pos = (pos + 2 * (i % 4)) % 8;
+                let ring = if i / 8 == 1 { 2 - ring } else { ring };
+                table[i][p] = ring * 8 + pos;
+                p += 1;
             }
+            i += 1;
         }
-        false
-    }
+        table
+    };
 
-    fn point_in_mill(&self, point: Point) -> bool {
-        if let Some(color) = self.board[point] {
-            self.forms_mill(point, color)
-        } else {
-            false
-        }
-    }
+    /// A quarter turn clockwise as a point permutation, `SYMMETRIES[1]`.
+    pub const ROTATE_90: [Point; 24] = Self::SYMMETRIES[1];
+    /// A left to right mirror image as a point permutation, `SYMMETRIES[4]`.
+    pub const MIRROR_HORIZONTAL: [Point; 24] = Self::SYMMETRIES[4];
+    /// Swapping the inner and outer squares as a point permutation, `SYMMETRIES[8]`.
+    pub const SWAP_RINGS: [Point; 24] = Self::SYMMETRIES[8];
 
-    fn all_pieces_in_mills(&self, color: Color) -> bool {
-        for i in 0..24 {
-            if self.board[i] == Some(color) && !self.point_in_mill(i) {
-                return false;
-            }
-        }
-        true
+    /// Returns the board turned a quarter turn clockwise.
+    /// Like all transforms here, this uses the numbering of the standard board.
+    pub fn rotate90(&self) -> [Option<Piece>; 24] {
+        self.transformed(&Self::ROTATE_90)
     }
 
-    fn count_pieces(&self, color: Color) -> u8 {
-        self.board
-            .iter()
-            .filter(|p| **p == Some(color))
-            .count() as u8
+    /// Returns the board mirrored left to right.
+    pub fn mirror_horizontal(&self) -> [Option<Piece>; 24] {
+        self.transformed(&Self::MIRROR_HORIZONTAL)
     }
 
-    fn are_adjacent(from: Point, to: Point) -> bool {
-        Self::NEIGHBORS[from].iter().any(|&n| n == to)
+    /// Returns the board with the inner and outer squares swapped.
+    pub fn swap_rings(&self) -> [Option<Piece>; 24] {
+        self.transformed(&Self::SWAP_RINGS)
     }
 
-    // oyuncunun şu anda YASAL hamlesi var mı?
-    fn player_can_move(&self, player: Player) -> bool {
-        let idx = Self::color_idx(player);
-
-        // önce: yerleştirme fazında mı?
-        if self.unplaced[idx] > 0 {
-            // boş yer varsa oynayabilir
-            return self.board.iter().any(|p| p.is_none());
-        }
+    /// Returns the lexicographically smallest board among all symmetric transforms,
+    /// ordering points as `None < Some(Black) < Some(White)`.
+    ///
+    /// Only the board is canonicalized. Whose turn it is and the piece counts are not part of
+    /// the result, so compare those separately if they matter.
+    /// Boards of Three and Six Men's Morris have no inner square, so only the 8 transforms
+    /// without the ring swap apply to them.
+    pub fn canonical(&self) -> [Option<Piece>; 24] {
+        self.symmetries()
+            .map(|sym| self.transformed(&sym))
+            .min()
+            .expect("the identity is always a symmetry")
+    }
 
-        // artık hareket fazında
-        let pieces = self.count_pieces(player);
+    // the transforms from `SYMMETRIES` that map the board of the variant onto itself
+    fn symmetries(&self) -> impl Iterator<Item = [Point; 24]> {
+        let variant = self.config.variant;
+        let count = variant.point_count();
+        Self::SYMMETRIES
+            .iter()
+            .map(move |sym| {
+                let mut sym = *sym;
+                if variant == Variant::Three {
+                    // the center is not part of a square and stays in place
+                    sym[8] = 8;
+                }
+                sym
+            })
+            .filter(move |sym| {
+                (0..count).all(|a| {
+                    sym[a] < count
+                        && (0..count).all(|b| {
+                            variant.are_adjacent(a, b) == variant.are_adjacent(sym[a], sym[b])
+                        })
+                })
+            })
+    }
 
-        // uçma durumu: 3 tas kaldiysa herhangi bos yere gidebilir
-        if pieces == 3 {
-            // tahtada kendi tasi varsa ve bos yer varsa, hamle var demektir
-            let has_own = self.board.iter().any(|p| *p == Some(player));
-            let has_empty = self.board.iter().any(|p| p.is_none());
-            return has_own && has_empty;
+    // the board after moving every piece from `p` to `permutation[p]`
+    fn transformed(&self, permutation: &[Point; 24]) -> [Option<Piece>; 24] {
+        let mut board = [None; 24];
+        for p in 0..self.config.variant.point_count() {
+            board[permutation[p]] = self.board[p];
         }
+        board
+    }
 
-        // normal hareket: komsusuna gidebilmeli
-        for from in 0..24 {
-            if self.board[from] == Some(player) {
-                for &n in Self::NEIGHBORS[from].iter() {
-                    if n < 24 && self.board[n].is_none() {
-                        return true;
-                    }
-                }
-            }
-        }
+    /// Creates a game of the given variant with its usual rules and an empty board.
+    pub fn with_variant(variant: Variant) -> Self {
+        Game::with_config(GameConfig::for_variant(variant))
+    }
 
-        false
+    /// Creates a standard game in which `first` places the first piece.
+    pub fn new_with_side_to_move(first: Player) -> Self {
+        Game::with_config(GameConfig {
+            first_player: first,
+            ..GameConfig::default()
+        })
     }
-}
 
-impl NmmGame for Game {
-    fn new() -> Self {
+    /// Creates a game with the given rules and an empty board.
+    pub fn with_config(config: GameConfig) -> Self {
         Game {
             board: [None; 24],
-            to_move: Player::White,
-            unplaced: [9, 9],
+            bits: [0, 0],
+            mills: 0,
+            board_hash: 0,
+            to_move: config.first_player,
+            unplaced: [config.variant.pieces(); 2],
             removed: [0, 0],
             must_remove: None,
+            last_mill: None,
+            quiet_moves: 0,
+            config,
+            resigned: None,
+            captures: [Vec::new(), Vec::new()],
             history: Vec::new(),
+            moves: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
-    fn action(&mut self, action: Action) -> Result<(), &'static str> {
-        // once noktalar gecerli mi diye bakalim
-        let check_point = |p: Point| -> Result<(), &'static str> {
-            if p >= 24 {
-                Err("Point out of range")
-            } else {
-                Ok(())
+    /// The points, lines and mills of the board the game is played on.
+    pub fn topology(&self) -> &'static Topology {
+        self.config.variant.topology()
+    }
+
+    /// Returns an independent copy of the game, including its history, e.g. to try out lines
+    /// of play. Nothing done to the copy affects `self`, and vice versa.
+    pub fn fork(&self) -> Game {
+        self.clone()
+    }
+
+    /// Returns the current position, without the history.
+    pub fn position(&self) -> Position {
+        Position {
+            board: self.board,
+            to_move: self.to_move,
+            unplaced: self.unplaced,
+            removed: self.removed,
+            must_remove: self.must_remove,
+            last_mill: self.last_mill,
+            quiet_moves: self.quiet_moves,
+            config: self.config,
+        }
+    }
+
+    /// Creates a game from a position, e.g. one returned by `position()`.
+    /// The game starts without history, so there is nothing to undo.
+    /// Fails if the position is inconsistent, e.g. if the piece counts do not add up.
+    pub fn from_position(position: Position) -> Result<Game, &'static str> {
+        let mut game = Game::with_config(position.config);
+        game.set_board(position.board);
+        game.to_move = position.to_move;
+        game.unplaced = position.unplaced;
+        game.removed = position.removed;
+        game.must_remove = position.must_remove;
+        game.last_mill = position.last_mill;
+        game.quiet_moves = position.quiet_moves;
+        game.validate()?;
+        Ok(game)
+    }
+
+    /// Creates a standard game from a set-up position, e.g. for a puzzle.
+    /// A pending removal counts as earned by any mill of that player on the board.
+    /// Like `from_position` the game has no history and fails if the setup is inconsistent.
+    pub fn from_setup(
+        board: [Option<Piece>; 24],
+        to_move: Player,
+        unplaced: [u8; 2],
+        removed: [u8; 2],
+        must_remove: Option<Player>,
+    ) -> Result<Game, &'static str> {
+        let last_mill = must_remove.and_then(|player| {
+            Game::MILLS
+                .into_iter()
+                .find(|mill| mill.iter().all(|&p| board[p] == Some(player)))
+        });
+        Game::from_position(Position {
+            board,
+            to_move,
+            unplaced,
+            removed,
+            must_remove,
+            last_mill,
+            ..Game::new().position()
+        })
+    }
+
+    /// Checks that the position could occur in a game and returns the first violated rule:
+    /// - pieces only stand on points of the variant,
+    /// - pieces on the board, unplaced and removed add up to the variant's count per player,
+    /// - the players placed alternately, starting with the first player of the rules,
+    /// - the last closed mill, if any, is one of the variant's mills,
+    /// - a removal is only pending for the side to move, right after it closed a mill.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let variant = self.config.variant;
+        if (variant.point_count()..24).any(|p| self.board[p].is_some()) {
+            return Err("Piece outside the board");
+        }
+        for color in [Color::White, Color::Black] {
+            let idx = Game::color_idx(color);
+            let total = self.count_pieces(color) as u32
+                + self.unplaced[idx] as u32
+                + self.removed[idx] as u32;
+            if total != variant.pieces() as u32 {
+                return Err("Piece counts do not add up");
             }
-        };
+        }
+        let first = self.unplaced[Self::color_idx(self.config.first_player)];
+        let second = self.unplaced[Self::color_idx(self.config.first_player.opposite())];
+        if first > second || second > first + 1 {
+            return Err("Players did not place alternately");
+        }
+        if let Some(mill) = self.last_mill {
+            if mill.iter().any(|&p| p >= variant.point_count()) {
+                return Err("Mill outside the board");
+            }
+            if !variant.mills().contains(&mill) {
+                return Err("Not a mill of the variant");
+            }
+        }
+        if let Some(player) = self.must_remove {
+            if player != self.to_move {
+                return Err("Removal pending for the wrong player");
+            }
+            let mill_closed = self
+                .last_mill
+                .is_some_and(|mill| mill.iter().all(|&p| self.board[p] == Some(player)));
+            if !mill_closed {
+                return Err("Removal pending without a mill");
+            }
+        }
+        Ok(())
+    }
 
-        // eger birinin tas sokmesi gerekiyorsa
-        if let Some(waiting) = self.must_remove {
-            // bu hamle remove olmali ve yapan da o olmali
-            if action.player != waiting {
-                return Err("This player must remove");
+    /// Returns the variant being played.
+    pub fn variant(&self) -> Variant {
+        self.config.variant
+    }
+
+    /// Returns the rules of this game.
+    pub fn config(&self) -> GameConfig {
+        self.config
+    }
+
+    /// Returns the phase of `player`.
+    ///
+    /// - `Placement` while the player has at least one unplaced piece.
+    /// - `Flying` once nothing is left to place and exactly three pieces are on the board,
+    ///   if flying is enabled.
+    /// - `Movement` otherwise.
+    ///
+    /// Since White begins, White's ninth placement moves White into `Movement` (or `Flying`)
+    /// while Black is still in `Placement` with one piece left. Black then places its last
+    /// piece, and only afterwards does White make its first move.
+    pub fn phase(&self, player: Player) -> Phase {
+        if self.unplaced[Self::color_idx(player)] > 0 {
+            Phase::Placement
+        } else if self.config.flying_enabled && self.count_pieces(player) == 3 {
+            Phase::Flying
+        } else {
+            Phase::Movement
+        }
+    }
+
+    /// Returns the overall result of the game.
+    /// Wins take precedence over draws.
+    pub fn result(&self) -> GameResult {
+        if let Some(loser) = self.resigned {
+            return GameResult::Win(loser.opposite());
+        }
+        // 1) 7 taş sökülmüş mü? (kurallara göre değişebilir)
+        let loss_threshold = self.config.loss_threshold;
+        if self.removed[Game::color_idx(Color::Black)] >= loss_threshold {
+            return GameResult::Win(Color::White);
+        }
+        if self.removed[Game::color_idx(Color::White)] >= loss_threshold {
+            return GameResult::Win(Color::Black);
+        }
+
+        // 2) fewer than three pieces can never close a mill again, whatever the threshold
+        if self.too_few_pieces(Color::Black) {
+            return GameResult::Win(Color::White);
+        }
+        if self.too_few_pieces(Color::White) {
+            return GameResult::Win(Color::Black);
+        }
+
+        // 3) sıradaki oyuncu oynayamıyorsa
+        if !self.player_can_move(self.to_move) {
+            if self.config.stalemate_is_draw {
+                return GameResult::Draw(DrawReason::Stalemate);
             }
-            match action.action {
-                ActionKind::Remove(p) => {
-                    check_point(p)?;
-                    // snapshot
-                    self.history.push(self.snapshot());
+            return GameResult::Win(self.to_move.opposite());
+        }
 
-                    let opponent = action.player.opposite();
-                    if self.board[p] != Some(opponent) {
-                        return Err("Can only remove opponent piece");
-                    }
+        if self.repetition_count() >= 3 {
+            return GameResult::Draw(DrawReason::Repetition);
+        }
+        if self.quiet_moves >= Self::NO_PROGRESS_LIMIT {
+            return GameResult::Draw(DrawReason::NoProgress);
+        }
 
-                    // eger rakibin mill disi tasi varsa milldekini sokemez
-                    if !self.all_pieces_in_mills(opponent) && self.point_in_mill(p) {
-                        // snapshot'i geri almak gerekir mi? Burada err'e düşmeden önce push ettik.
-                        // kolay yol: en basta push etmemekti, ama simdi basitçe sonu geri cekelim.
-                        self.history.pop();
-                        return Err("Cannot remove a piece in a mill");
+        GameResult::Ongoing
+    }
+
+    /// Returns true once the game has been won or drawn.
+    pub fn is_game_over(&self) -> bool {
+        self.result() != GameResult::Ongoing
+    }
+
+    /// Returns why the game was won, or `None` if nobody has won.
+    pub fn win_reason(&self) -> Option<WinReason> {
+        match self.result() {
+            GameResult::Win(_) if self.resigned.is_some() => Some(WinReason::Resignation),
+            GameResult::Win(_)
+                if self
+                    .removed
+                    .iter()
+                    .any(|&r| r >= self.config.loss_threshold) =>
+            {
+                Some(WinReason::SevenRemoved)
+            }
+            GameResult::Win(winner) if self.too_few_pieces(winner.opposite()) => {
+                Some(WinReason::TooFewPieces)
+            }
+            GameResult::Win(_) => Some(WinReason::NoLegalMoves),
+            _ => None,
+        }
+    }
+
+    /// Describes the state of the game in one line, e.g.
+    /// `White to move (placement, 5 left); Black to move next; no winner` or
+    /// `Black wins by capturing 7`.
+    pub fn summary(&self) -> String {
+        let name = |player: Player| match player {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        let player = self.to_move;
+        match self.result() {
+            GameResult::Win(winner) => match self.win_reason() {
+                Some(WinReason::SevenRemoved) => format!(
+                    "{} wins by capturing {}",
+                    name(winner),
+                    self.removed[Self::color_idx(winner.opposite())]
+                ),
+                Some(WinReason::TooFewPieces) => format!(
+                    "{} wins, {} has {} pieces left",
+                    name(winner),
+                    name(winner.opposite()),
+                    self.count_pieces(winner.opposite())
+                ),
+                Some(WinReason::Resignation) => {
+                    format!(
+                        "{} wins, {} resigned",
+                        name(winner),
+                        name(winner.opposite())
+                    )
+                }
+                _ => format!("{} wins, {} cannot move", name(winner), name(player)),
+            },
+            GameResult::Draw(DrawReason::Repetition) => "Draw by repetition".to_string(),
+            GameResult::Draw(DrawReason::NoProgress) => "Draw, no progress".to_string(),
+            GameResult::Draw(DrawReason::Stalemate) => {
+                format!("Draw, {} cannot move", name(player))
+            }
+            GameResult::Ongoing => {
+                let phase = match self.phase(player) {
+                    Phase::Placement => {
+                        format!("placement, {} left", self.remaining_unplaced(player))
                     }
+                    Phase::Movement => "movement".to_string(),
+                    Phase::Flying => "flying".to_string(),
+                };
+                let turn = match self.must_remove {
+                    Some(_) => "to remove a piece",
+                    None => "to move",
+                };
+                format!(
+                    "{} {turn} ({phase}); {} to move next; no winner",
+                    name(player),
+                    name(player.opposite())
+                )
+            }
+        }
+    }
 
-                    self.board[p] = None;
-                    let opp_idx = Game::color_idx(opponent);
-                    self.removed[opp_idx] += 1;
-                    self.must_remove = None;
-                    self.to_move = opponent;
-                    Ok(())
+    /// Returns true if the game ended in a draw.
+    pub fn is_draw(&self) -> bool {
+        matches!(self.result(), GameResult::Draw(_))
+    }
+
+    /// Heuristic check for a position that is likely drawn: both players are flying and
+    /// neither could close a mill with its next move. This is not a rule of the game and
+    /// `result()` does not use it. Either side may still set up a mill later on.
+    pub fn is_material_drawish(&self) -> bool {
+        self.must_remove.is_none()
+            && [Color::White, Color::Black].into_iter().all(|color| {
+                self.phase(color) == Phase::Flying && self.closable_mills(color).is_empty()
+            })
+    }
+
+    /// Returns how often the current position has occurred in this game, including now.
+    /// Positions count as the same if the board, the side to move, a pending removal and the
+    /// placed and removed pieces match, as for the repetition draw.
+    pub fn repetition_count(&self) -> u32 {
+        let current = self.snapshot();
+        1 + self
+            .history
+            .iter()
+            .filter(|snap| snap.same_position(&current))
+            .count() as u32
+    }
+
+    /// Returns a 64-bit Zobrist hash of the current position.
+    ///
+    /// The hash covers the board, the side to move, a pending removal and the number of
+    /// unplaced pieces per color. It does not depend on how the position was reached,
+    /// and it is the same across runs and processes.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash_with_board(&self.board)
+    }
+
+    /// Like `zobrist_hash`, but for the `canonical()` board, so that symmetric positions
+    /// share the same hash.
+    pub fn canonical_hash(&self) -> u64 {
+        self.hash_with_board(&self.canonical())
+    }
+
+    /// Returns the same hash as `zobrist_hash`, without looking at every point: the keys of the
+    /// pieces are updated whenever a point changes, and undo restores them.
+    pub fn current_hash(&self) -> u64 {
+        self.board_hash ^ self.state_hash()
+    }
+
+    fn hash_with_board(&self, board: &[Option<Piece>; 24]) -> u64 {
+        let mut hash = 0;
+        for (point, piece) in board.iter().enumerate() {
+            if let Some(color) = piece {
+                hash ^= ZOBRIST_KEYS[point * 2 + Self::color_idx(*color)];
+            }
+        }
+        hash ^ self.state_hash()
+    }
+
+    // the keys of everything but the board
+    fn state_hash(&self) -> u64 {
+        let mut hash = 0;
+        if self.to_move == Color::Black {
+            hash ^= ZOBRIST_KEYS[ZOBRIST_BLACK_TO_MOVE];
+        }
+        if let Some(player) = self.must_remove {
+            hash ^= ZOBRIST_KEYS[ZOBRIST_MUST_REMOVE + Self::color_idx(player)];
+        }
+        for idx in 0..2 {
+            let unplaced = self.unplaced[idx] as usize % ZOBRIST_UNPLACED_MAX;
+            hash ^= ZOBRIST_KEYS[ZOBRIST_UNPLACED + idx * ZOBRIST_UNPLACED_MAX + unplaced];
+        }
+        hash
+    }
+
+    /// Returns the player whose action is expected next.
+    /// While a removal is pending this is the player who has to remove.
+    pub fn to_move(&self) -> Player {
+        self.to_move
+    }
+
+    /// Returns the player who closed a mill and now has to remove an opposing piece, if any.
+    pub fn must_remove(&self) -> Option<Player> {
+        self.must_remove
+    }
+
+    /// Returns how many pieces `player` still has to place.
+    pub fn remaining_unplaced(&self, player: Player) -> u8 {
+        self.unplaced[Self::color_idx(player)]
+    }
+
+    /// Returns how many placements both players together still have to make.
+    pub fn placements_remaining_total(&self) -> u8 {
+        self.unplaced[0] + self.unplaced[1]
+    }
+
+    /// Returns how many pieces each player has on the board, White first.
+    pub fn pieces_on_board(&self) -> (u8, u8) {
+        (
+            self.count_pieces(Color::White),
+            self.count_pieces(Color::Black),
+        )
+    }
+
+    /// Returns how many pieces `player` has left to play with: on the board or still to place.
+    /// Once this drops below three the player has lost (with the standard loss threshold).
+    pub fn pieces_remaining(&self, player: Player) -> u8 {
+        self.count_pieces(player) + self.remaining_unplaced(player)
+    }
+
+    /// Returns true once both players have placed all their pieces.
+    ///
+    /// Placements alternate, so the player who started places their last piece first,
+    /// but nobody moves before the opponent has placed theirs as well. `phase()` reports
+    /// the phase of a single player and may therefore differ for that one turn.
+    pub fn in_movement_phase(&self) -> bool {
+        self.placements_remaining_total() == 0
+    }
+
+    /// Returns how many pieces of `player` were removed by the opponent.
+    /// Once this reaches the loss threshold of the rules (7 by default), the opponent has won.
+    pub fn removed_count(&self, player: Player) -> u8 {
+        self.removed[Self::color_idx(player)]
+    }
+
+    /// Returns the mill closed by the most recent action, or `None` if it closed no mill.
+    /// If one action closed two mills at once, the one listed first in the variant's
+    /// `mills()` is returned.
+    pub fn last_mill(&self) -> Option<[Point; 3]> {
+        self.last_mill
+    }
+
+    /// Returns true if the most recent action closed a mill but no opponent piece could be
+    /// removed, e.g. because the mill removal policy protects all of them or the opponent
+    /// has no pieces on the board. The mill then earns nothing: the turn passes at once and
+    /// the move log shows no removal after the mill.
+    pub fn mill_without_removal(&self) -> bool {
+        self.last_mill.is_some() && self.must_remove.is_none()
+    }
+
+    /// Returns every point together with its piece and whether that piece is in a mill.
+    /// Use `points()` for just the pieces.
+    pub fn board_view(&self) -> [Square; 24] {
+        std::array::from_fn(|point| Square {
+            point,
+            piece: self.board[point],
+            in_mill: self.is_mill_point(point),
+        })
+    }
+
+    /// Draws the board like `Display`, with White in bright white and Black in bright red.
+    /// Pieces in a closed mill are also bold and underlined.
+    pub fn render_ansi(&self) -> String {
+        self.render_colored(true)
+    }
+
+    /// Draws the board like `render_ansi` if `colors` is set, otherwise exactly like `Display`,
+    /// e.g. `game.render_colored(std::io::stdout().is_terminal())`.
+    pub fn render_colored(&self, colors: bool) -> String {
+        let mut board = String::new();
+        self.write_board(&mut board, |p| {
+            let c = Game::piece_char(self.board[p]);
+            match self.board[p] {
+                Some(piece) if colors => {
+                    let mill = if self.is_mill_point(p) { "1;4;" } else { "" };
+                    let color = if piece == Color::White { 97 } else { 91 };
+                    format!("\x1b[{mill}{color}m{c}\x1b[0m")
                 }
-                _ => Err("Must remove a piece"),
+                _ => c.to_string(),
             }
-        } else {
-            // normal sıra kontrolü
-            if action.player != self.to_move {
-                return Err("Not this player's turn");
+        })
+        .expect("writing to a String cannot fail");
+        board
+    }
+
+    /// Draws the board like `Display`, but with box-drawing lines, `○` for White and `●` for
+    /// Black. Every character is one column wide in a monospace font.
+    /// Unlike `Display`, Twelve Men's Morris shows its diagonal lines, which takes 11 rows.
+    pub fn render_unicode(&self) -> String {
+        // (row, column) of every point in the templates
+        #[rustfmt::skip]
+        const GRID: [(usize, usize); 24] = [
+            (0, 0), (0, 6), (0, 12), (3, 12), (6, 12), (6, 6), (6, 0), (3, 0),
+            (1, 2), (1, 6), (1, 10), (3, 10), (5, 10), (5, 6), (5, 2), (3, 2),
+            (2, 4), (2, 6), (2, 8), (3, 8), (4, 8), (4, 6), (4, 4), (3, 4),
+        ];
+        #[rustfmt::skip]
+        const TWELVE_GRID: [(usize, usize); 24] = [
+            (0, 0), (0, 6), (0, 12), (5, 12), (10, 12), (10, 6), (10, 0), (5, 0),
+            (2, 2), (2, 6), (2, 10), (5, 10), (8, 10), (8, 6), (8, 2), (5, 2),
+            (4, 4), (4, 6), (4, 8), (5, 8), (6, 8), (6, 6), (6, 4), (5, 4),
+        ];
+        #[rustfmt::skip]
+        const THREE_GRID: [(usize, usize); 9] = [
+            (0, 0), (0, 2), (0, 4), (2, 4), (4, 4), (4, 2), (4, 0), (2, 0), (2, 2),
+        ];
+        let (template, grid): (&[&str], &[(usize, usize)]) = match self.config.variant {
+            Variant::Three => (&["┌─┬─┐", "│╲│╱│", "├─┼─┤", "│╱│╲│", "└─┴─┘"], &THREE_GRID),
+            Variant::Six => (
+                &[
+                    "┌─────┬─────┐",
+                    "│ ┌───┴───┐ │",
+                    "│ │       │ │",
+                    "├─┤       ├─┤",
+                    "│ │       │ │",
+                    "│ └───┬───┘ │",
+                    "└─────┴─────┘",
+                ],
+                &GRID[..16],
+            ),
+            Variant::Twelve => (
+                &[
+                    "┌─────┬─────┐",
+                    "│╲    │    ╱│",
+                    "│ ┌───┼───┐ │",
+                    "│ │╲  │  ╱│ │",
+                    "│ │ ┌─┴─┐ │ │",
+                    "├─┼─┤   ├─┼─┤",
+                    "│ │ └─┬─┘ │ │",
+                    "│ │╱  │  ╲│ │",
+                    "│ └───┼───┘ │",
+                    "│╱    │    ╲│",
+                    "└─────┴─────┘",
+                ],
+                &TWELVE_GRID,
+            ),
+            Variant::Nine => (
+                &[
+                    "┌─────┬─────┐",
+                    "│ ┌───┼───┐ │",
+                    "│ │ ┌─┴─┐ │ │",
+                    "├─┼─┤   ├─┼─┤",
+                    "│ │ └─┬─┘ │ │",
+                    "│ └───┼───┘ │",
+                    "└─────┴─────┘",
+                ],
+                &GRID,
+            ),
+        };
+        let mut rows: Vec<Vec<char>> = template.iter().map(|row| row.chars().collect()).collect();
+        for (point, &(row, column)) in grid.iter().enumerate() {
+            match self.board[point] {
+                Some(Color::White) => rows[row][column] = '○',
+                Some(Color::Black) => rows[row][column] = '●',
+                None => {}
             }
+        }
+        rows.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-            let idx = Game::color_idx(action.player);
-            match action.action {
-                ActionKind::Place(p) => {
-                    check_point(p)?;
-                    if self.unplaced[idx] == 0 {
-                        return Err("No pieces left to place");
-                    }
-                    if self.board[p].is_some() {
-                        return Err("Point already occupied");
-                    }
+    /// Returns the board as 24 characters in point order: `W`, `B` or `.` for an empty point.
+    pub fn board_string(&self) -> String {
+        self.board
+            .iter()
+            .map(|&piece| Game::piece_char(piece))
+            .collect()
+    }
 
-                    // snapshot
-                    self.history.push(self.snapshot());
-
-                    self.board[p] = Some(action.player);
-                    self.unplaced[idx] -= 1;
-
-                    if self.forms_mill(p, action.player) {
-                        // Check if player can actually remove any piece
-                        let opponent = action.player.opposite();
-                        let all_opponent_in_mills = self.all_pieces_in_mills(opponent);
-                        let can_remove = (0..24).any(|i| {
-                            if self.board[i] == Some(opponent) {
-                                all_opponent_in_mills || !self.point_in_mill(i)
-                            } else {
-                                false
-                            }
-                        });
-                        if can_remove {
-                            self.must_remove = Some(action.player);
-                        } else {
-                            // Can't remove, so continue the game
-                            self.to_move = action.player.opposite();
+    /// Replaces the board with one written like `board_string()`. Nothing else changes, so
+    /// the piece counts may no longer add up; see `validate()`.
+    /// On error the board is left as it was.
+    pub fn set_board_string(&mut self, s: &str) -> Result<(), LoadError> {
+        if s.chars().count() != 24 {
+            return Err(LoadError::Parse("Board must have 24 points"));
+        }
+        let mut board = [None; 24];
+        for (point, c) in s.chars().enumerate() {
+            board[point] =
+                Game::piece_from_char(c).ok_or(LoadError::Parse("Invalid point character"))?;
+        }
+        self.set_board(board);
+        Ok(())
+    }
+
+    /// Iterates over the occupied points and the pieces on them, in point order.
+    pub fn pieces(&self) -> impl Iterator<Item = (Point, Piece)> + '_ {
+        self.board
+            .iter()
+            .enumerate()
+            .filter_map(|(point, piece)| piece.map(|piece| (point, piece)))
+    }
+
+    /// Iterates over the points occupied by `color`, in point order.
+    pub fn pieces_of(&self, color: Color) -> impl Iterator<Item = Point> + '_ {
+        self.pieces()
+            .filter(move |&(_, piece)| piece == color)
+            .map(|(point, _)| point)
+    }
+
+    /// Returns the mill lines with exactly two pieces of `color` and one empty point.
+    pub fn open_mills(&self, color: Color) -> Vec<[Point; 3]> {
+        self.config
+            .variant
+            .mills()
+            .iter()
+            .filter(|mill| {
+                let own = mill
+                    .iter()
+                    .filter(|&&p| self.board[p] == Some(color))
+                    .count();
+                let empty = mill.iter().filter(|&&p| self.board[p].is_none()).count();
+                own == 2 && empty == 1
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns the open mills that `color` could close with its next placement or move,
+    /// ignoring whose turn it is. A move only counts if the piece comes from outside the mill.
+    pub fn closable_mills(&self, color: Color) -> Vec<[Point; 3]> {
+        let phase = self.phase(color);
+        self.open_mills(color)
+            .into_iter()
+            .filter(|mill| {
+                let target = *mill.iter().find(|&&p| self.board[p].is_none()).unwrap();
+                phase == Phase::Placement
+                    || self.pieces_of(color).any(|from| {
+                        !mill.contains(&from)
+                            && (phase == Phase::Flying
+                                || self.config.variant.are_adjacent(from, target))
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the mills that the opponent of `defender` threatens to close with its next
+    /// placement or move, i.e. the open mills `defender` should block.
+    /// Like `closable_mills`, this ignores whose turn it is.
+    pub fn opponent_threats(&self, defender: Player) -> Vec<[Point; 3]> {
+        self.closable_mills(defender.opposite())
+    }
+
+    /// Finds swinging ("running") mills of `color`: a piece that sits in a closed mill and can
+    /// move to an adjacent empty point where it closes a second mill. Moving it back and forth
+    /// then closes a mill every turn.
+    ///
+    /// Each entry is the shuttling point, the closed mill it is part of and the mill it
+    /// closes by moving. Flying is not taken into account.
+    pub fn swinging_mills(&self, color: Color) -> Vec<(Point, [Point; 3], [Point; 3])> {
+        let variant = self.config.variant;
+        let owned = |p: Point| self.board[p] == Some(color);
+        let mut found = Vec::new();
+        for closed in variant
+            .mills()
+            .iter()
+            .filter(|mill| mill.iter().all(|&p| owned(p)))
+        {
+            for &shuttle in closed {
+                for target in variant.neighbors(shuttle) {
+                    if self.board[target].is_some() {
+                        continue;
+                    }
+                    for open in variant.mills().iter().filter(|mill| mill.contains(&target)) {
+                        if !open.contains(&shuttle) && open.iter().all(|&p| p == target || owned(p))
+                        {
+                            found.push((shuttle, *closed, *open));
                         }
-                    } else {
-                        self.to_move = action.player.opposite();
                     }
-                    Ok(())
                 }
-                ActionKind::Move(from, to) => {
-                    check_point(from)?;
-                    check_point(to)?;
+            }
+        }
+        found
+    }
 
-                    if self.unplaced[idx] > 0 {
-                        return Err("Must place all pieces before moving");
-                    }
-                    if self.board[from] != Some(action.player) {
-                        return Err("No piece of this player at source");
-                    }
-                    if self.board[to].is_some() {
-                        return Err("Destination not empty");
-                    }
+    /// Returns true if there is a piece on `point` and none of its neighbors is empty.
+    /// Flying is not taken into account. Out-of-range points are never blocked.
+    pub fn is_blocked(&self, point: Point) -> bool {
+        self.board.get(point).is_some_and(|piece| piece.is_some())
+            && self
+                .config
+                .variant
+                .neighbors(point)
+                .into_iter()
+                .all(|n| self.board[n].is_some())
+    }
 
-                    let flying = self.count_pieces(action.player) == 3;
-                    if !flying && !Game::are_adjacent(from, to) {
-                        return Err("Points not adjacent");
-                    }
+    /// Returns how many placements or moves `player` could make in the current position,
+    /// regardless of whose turn it is. Pending removals are not counted.
+    pub fn mobility(&self, player: Player) -> u32 {
+        let empty = self.empty_points().count() as u32;
+        match self.phase(player) {
+            Phase::Placement => empty,
+            Phase::Flying => self.count_pieces(player) as u32 * empty,
+            Phase::Movement => self
+                .pieces_of(player)
+                .flat_map(|from| self.config.variant.neighbors(from))
+                .filter(|&to| self.board[to].is_none())
+                .count() as u32,
+        }
+    }
 
-                    // snapshot
-                    self.history.push(self.snapshot());
-
-                    self.board[from] = None;
-                    self.board[to] = Some(action.player);
-
-                    if self.forms_mill(to, action.player) {
-                        // Check if player can actually remove any piece
-                        let opponent = action.player.opposite();
-                        let all_opponent_in_mills = self.all_pieces_in_mills(opponent);
-                        let can_remove = (0..24).any(|i| {
-                            if self.board[i] == Some(opponent) {
-                                all_opponent_in_mills || !self.point_in_mill(i)
-                            } else {
-                                false
-                            }
-                        });
-                        if can_remove {
-                            self.must_remove = Some(action.player);
-                        } else {
-                            // Can't remove, so continue the game
-                            self.to_move = action.player.opposite();
-                        }
-                    } else {
-                        self.to_move = action.player.opposite();
-                    }
+    /// Returns the empty points next to at least one piece of `player`, in ascending order,
+    /// regardless of whose turn it is. Uses the adjacency of the game's variant, so the
+    /// diagonals of Twelve Men's Morris count as well.
+    pub fn frontier(&self, player: Player) -> Vec<Point> {
+        let adjacency = self.config.variant.adjacency();
+        let reach = self
+            .pieces_of(player)
+            .fold(0, |reach, from| reach | adjacency[from]);
+        self.empty_points()
+            .filter(|&p| reach & (1 << p) != 0)
+            .collect()
+    }
 
-                    Ok(())
+    /// Returns how many mill lines of the variant are fully occupied by `color`.
+    /// Each line counts once, even if it shares a point with another mill.
+    pub fn count_mills(&self, color: Color) -> u8 {
+        self.config
+            .variant
+            .mills()
+            .iter()
+            .filter(|mill| mill.iter().all(|&p| self.board[p] == Some(color)))
+            .count() as u8
+    }
+
+    /// Sums the positional weights of the points `color` occupies: `POINT_VALUES` on the
+    /// standard board, the number of neighbors of the point in other variants.
+    pub fn positional_score(&self, color: Color) -> i32 {
+        let variant = self.config.variant;
+        self.pieces_of(color)
+            .map(|p| match variant {
+                Variant::Nine => Game::POINT_VALUES[p],
+                _ => variant.neighbors(p).len() as i32,
+            })
+            .sum()
+    }
+
+    /// Returns true if the piece on `point` is part of a closed mill.
+    /// Empty points and points outside the board are never mill points.
+    pub fn is_mill_point(&self, point: Point) -> bool {
+        point < 24 && self.mill_points() & 1 << point != 0
+    }
+
+    /// Returns true if a piece of `color` on `point` would complete a mill with the pieces
+    /// `color` already has on the board, i.e. if placing it there closes a mill. Whatever
+    /// stands on `point` now is ignored. False for points outside the board.
+    pub fn would_form_mill(&self, point: Point, color: Color) -> bool {
+        point < self.config.variant.point_count()
+            && self.closes_mill(Action {
+                player: color,
+                action: ActionKind::Place(point),
+            })
+    }
+
+    /// Returns the mills that are closed right now, by either color, in the order of
+    /// `Variant::mills`.
+    pub fn closed_mills(&self) -> Vec<[Point; 3]> {
+        let mills = self.config.variant.mills();
+        (0..mills.len())
+            .filter(|&i| self.mills & 1 << i != 0)
+            .map(|i| mills[i])
+            .collect()
+    }
+
+    /// Returns all actions the next player may take.
+    /// While a removal is pending these are the possible removals.
+    /// Once the game is over there are none, even if pieces could still move.
+    pub fn legal_moves(&self) -> Vec<Action> {
+        let player = self.to_move;
+        let mut moves = Vec::new();
+        if self.is_game_over() {
+            return moves;
+        }
+        if self.must_remove.is_some() {
+            for p in self.legal_removals() {
+                moves.push(Action {
+                    player,
+                    action: ActionKind::Remove(p),
+                });
+            }
+            return moves;
+        }
+        self.pseudo_legal_moves(player)
+    }
+
+    /// Returns the placements or moves `player` could make if it were their turn, following
+    /// their phase, adjacency and occupancy. Whose turn it is, pending removals and the result
+    /// of the game are ignored, and removals are never included.
+    pub fn pseudo_legal_moves(&self, player: Player) -> Vec<Action> {
+        let mut moves = Vec::new();
+        let phase = self.phase(player);
+        for to in self.empty_points() {
+            if phase == Phase::Placement {
+                moves.push(Action {
+                    player,
+                    action: ActionKind::Place(to),
+                });
+                continue;
+            }
+            for from in 0..24 {
+                let action = Action {
+                    player,
+                    action: ActionKind::Move(from, to),
+                };
+                if self.board[from] == Some(player)
+                    && (phase == Phase::Flying || self.config.variant.are_adjacent(from, to))
+                    && !self.recloses_mill(action)
+                {
+                    moves.push(action);
                 }
-                ActionKind::Remove(_) => {
-                    Err("Remove not allowed now")
+            }
+        }
+        moves
+    }
+
+    /// Returns the legal actions that close a mill, for extending a search along forcing lines.
+    /// While a removal is pending, the removals are returned since they complete the capture.
+    pub fn forcing_moves(&self) -> Vec<Action> {
+        let moves = self.legal_moves();
+        if self.must_remove.is_some() {
+            return moves;
+        }
+        moves
+            .into_iter()
+            .filter(|&action| self.closes_mill(action))
+            .collect()
+    }
+
+    /// Returns the legal actions ordered for searching: actions that close a mill first, then
+    /// actions that occupy the empty point of an open opponent mill, then the rest.
+    /// Within each group the order of `legal_moves()` is kept.
+    pub fn ordered_moves(&self) -> Vec<Action> {
+        let blocking = self
+            .open_mills(self.to_move.opposite())
+            .iter()
+            .flatten()
+            .filter(|&&p| self.board[p].is_none())
+            .fold(0u32, |points, &p| points | 1 << p);
+        let mut moves = self.legal_moves();
+        moves.sort_by_key(|&action| match action.action {
+            _ if self.closes_mill(action) => 0,
+            ActionKind::Place(to) | ActionKind::Move(_, to) if blocking & 1 << to != 0 => 1,
+            _ => 2,
+        });
+        moves
+    }
+
+    /// Returns true if every legal action of the side to move lets the opponent close a mill
+    /// with its reply. A removal after a closed mill belongs to the same turn, so the turn only
+    /// concedes a mill if every removal does. False once the game is over.
+    pub fn must_concede_mill(&self) -> bool {
+        let player = self.to_move;
+        let moves = self.legal_moves();
+        let mut game = self.clone();
+        !moves.is_empty()
+            && moves.into_iter().all(|action| {
+                game.action(action).expect("legal action");
+                let concedes = if game.to_move == player {
+                    game.must_concede_mill()
+                } else {
+                    game.legal_moves()
+                        .into_iter()
+                        .any(|reply| game.closes_mill(reply))
+                };
+                game.undo().expect("action was just applied");
+                concedes
+            })
+    }
+
+    /// Returns true if a removal is pending and the piece on `point` may be taken: it belongs to
+    /// the opponent of the removing player and the mill removal policy allows taking it.
+    pub fn can_remove(&self, point: Point) -> bool {
+        match self.must_remove {
+            Some(player) => {
+                self.board.get(point) == Some(&Some(player.opposite())) && self.is_removable(point)
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the points whose pieces may be removed right now, or an empty list if no
+    /// removal is pending. Pieces in mills are only included if all opponent pieces are in mills.
+    pub fn legal_removals(&self) -> Vec<Point> {
+        match self.must_remove {
+            Some(player) => self
+                .pieces_of(player.opposite())
+                .filter(|&p| self.can_remove(p))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the only legal action, or `None` if there is a choice or no action at all.
+    /// While a removal is pending, this is the only removable piece.
+    pub fn forced_move(&self) -> Option<Action> {
+        match self.legal_moves()[..] {
+            [action] => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Returns the empty points the side to move may place a piece on, or nothing if that side
+    /// is not placing pieces right now (movement, removal, game over).
+    pub fn placement_targets(&self) -> Vec<Point> {
+        if self.must_remove.is_some()
+            || self.phase(self.to_move) != Phase::Placement
+            || self.is_game_over()
+        {
+            return Vec::new();
+        }
+        self.empty_points().collect()
+    }
+
+    /// Returns the points the piece on `from` may move to, or nothing if it is not a piece of
+    /// the side to move or that side is not moving pieces right now (placement, removal, game over).
+    pub fn moves_from(&self, from: Point) -> Vec<Point> {
+        if self.board.get(from) != Some(&Some(self.to_move)) {
+            return Vec::new();
+        }
+        self.legal_moves()
+            .into_iter()
+            .filter_map(|action| match action.action {
+                ActionKind::Move(f, to) if f == from => Some(to),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns every legal action together with the position it leads to.
+    pub fn successors(&self) -> Vec<(Action, Position)> {
+        let mut game = self.clone();
+        self.legal_moves()
+            .into_iter()
+            .map(|action| {
+                game.action(action).expect("generated action is legal");
+                let position = game.position();
+                game.undo().expect("action was just applied");
+                (action, position)
+            })
+            .collect()
+    }
+
+    /// Counts the leaf nodes of the tree of legal actions `depth` plies deep.
+    /// Removals count as plies of their own.
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().perft_mut(depth)
+    }
+
+    fn perft_mut(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for action in moves {
+            self.action(action).expect("generated action is legal");
+            nodes += self.perft_mut(depth - 1);
+            self.undo().expect("action was just applied");
+        }
+        nodes
+    }
+
+    /// Exports the tree of legal actions `depth` plies deep as a Graphviz `digraph`.
+    ///
+    /// Nodes are positions, named by their `zobrist_hash` in hex and labeled with the board
+    /// like `board_string()` and the side to move. A position reached by several orders of
+    /// actions appears once; symmetric positions stay apart. Edges are labeled with the action.
+    pub fn to_dot(&self, depth: u32) -> String {
+        let node = |game: &Game| {
+            format!(
+                "  \"{:016x}\" [label=\"{}\\n{} to move\"];\n",
+                game.current_hash(),
+                game.board_string(),
+                game.to_move
+            )
+        };
+        let mut dot = String::from("digraph game {\n");
+        dot.push_str(&node(self));
+        let mut seen = HashSet::from([self.current_hash()]);
+        let mut queue = VecDeque::from([(self.clone(), 0)]);
+        while let Some((game, level)) = queue.pop_front() {
+            if level == depth {
+                continue;
+            }
+            for action in game.legal_moves() {
+                let mut next = game.clone();
+                next.action(action).expect("generated action is legal");
+                dot.push_str(&format!(
+                    "  \"{:016x}\" -> \"{:016x}\" [label=\"{action}\"];\n",
+                    game.current_hash(),
+                    next.current_hash()
+                ));
+                if seen.insert(next.current_hash()) {
+                    dot.push_str(&node(&next));
+                    queue.push_back((next, level + 1));
                 }
             }
         }
+        dot.push_str("}\n");
+        dot
     }
 
-    fn undo(&mut self) -> Result<(), &'static str> {
-        if let Some(snap) = self.history.pop() {
-            self.board = snap.board;
-            self.to_move = snap.to_move;
-            self.unplaced = snap.unplaced;
-            self.removed = snap.removed;
-            self.must_remove = snap.must_remove;
-            Ok(())
-        } else {
-            Err("No action to undo")
+    /// Reapplies the last action taken back by `undo()`.
+    ///
+    /// Every `undo()` keeps the position it leaves, so several undos can be redone in turn.
+    /// Applying a new action with `action()` discards everything that could be redone.
+    /// This fails if there is nothing to redo.
+    pub fn redo(&mut self) -> Result<(), ActionError> {
+        let (snap, action) = self.redo_stack.pop().ok_or(ActionError::NothingToRedo)?;
+        self.history.push(self.snapshot());
+        self.moves.push(action);
+        if let ActionKind::Remove(p) = action.action {
+            self.captures[Game::color_idx(action.player)].push(p);
         }
+        self.restore(snap);
+        Ok(())
     }
 
-    fn points(&self) -> &[Option<Piece>; 24] {
-        &self.board
+    /// Returns the points where `player` removed opponent pieces, in the order of removal.
+    /// Only removals of this game's history count, not those of a starting position.
+    pub fn captures_by(&self, player: Player) -> &[Point] {
+        &self.captures[Game::color_idx(player)]
     }
 
-    fn winner(&self) -> Option<Player> {
-        // 1) 7 taş sökülmüş mü?
-        if self.removed[Game::color_idx(Color::Black)] >= 7 {
-            return Some(Color::White);
+    /// Ends the game with a win for the opponent of `player`, e.g. when `player` resigns or
+    /// runs out of time. This takes precedence over the board: `result()` reports the win even
+    /// if the position would be won by `player`. No action can be applied afterwards, but
+    /// `undo()` takes the resignation back. Does nothing if the game is already over.
+    pub fn resign(&mut self, player: Player) {
+        if !self.is_game_over() {
+            self.resigned = Some(player);
+            self.redo_stack.clear();
+        }
+    }
+
+    /// Takes back up to `count` actions with `undo()` and returns how many were undone.
+    /// A resignation counts as an action.
+    /// This fails only if `count` is not zero and there is nothing to undo.
+    pub fn undo_n(&mut self, count: usize) -> Result<usize, ActionError> {
+        let undone = count.min(self.history.len() + self.resigned.is_some() as usize);
+        if count > 0 && undone == 0 {
+            return Err(ActionError::NothingToUndo);
         }
-        if self.removed[Game::color_idx(Color::White)] >= 7 {
-            return Some(Color::Black);
+        for _ in 0..undone {
+            self.undo()?;
         }
+        Ok(undone)
+    }
 
-        // 2) sıradaki oyuncu oynayamıyorsa
-        if !self.player_can_move(self.to_move) {
-            return Some(self.to_move.opposite());
+    /// Takes back every action, returning to the position the game started from
+    /// (the `new()` position for games started with `new()`).
+    /// The undone actions can be replayed with `redo()` until a new action is applied.
+    pub fn reset(&mut self) {
+        while self.undo().is_ok() {}
+    }
+
+    /// Applies `action` like `action()` and describes what happened, in order.
+    pub fn action_events(&mut self, action: Action) -> Result<Vec<GameEvent>, ActionError> {
+        self.action(action)?;
+        let player = action.player;
+        let mut events = vec![match action.action {
+            ActionKind::Place(point) => GameEvent::Placed { player, point },
+            ActionKind::Move(from, to) => GameEvent::Moved { player, from, to },
+            ActionKind::Remove(point) => GameEvent::Removed { player, point },
+        }];
+        if let Some(mill) = self.last_mill {
+            events.push(if self.must_remove.is_some() {
+                GameEvent::MillFormed { player, mill }
+            } else {
+                GameEvent::MillWithoutRemoval { player, mill }
+            });
+        }
+        if self.to_move != player {
+            events.push(GameEvent::TurnPassed {
+                player: self.to_move,
+            });
         }
+        Ok(events)
+    }
 
-        None
+    /// Writes `action` in coordinate notation for the current position: `a1` for a placement,
+    /// `a1-a4` for a move and `g7` for a removal. A placement or move that closes a mill and
+    /// forces a removal gets an `x`, so together with the removal it reads `a1xg7`.
+    /// Panics if a point of `action` is off the board.
+    pub fn to_notation(&self, action: &Action) -> String {
+        let mut notation = match action.action {
+            ActionKind::Place(p) | ActionKind::Remove(p) => point_to_coord(p),
+            ActionKind::Move(from, to) => {
+                format!("{}-{}", point_to_coord(from), point_to_coord(to))
+            }
+        };
+        if self.closes_mill(*action) {
+            let mut after = self.clone();
+            if after.action(*action).is_ok() && after.must_remove.is_some() {
+                notation.push('x');
+            }
+        }
+        notation
     }
-}
 
-// For grading this assignment, the tests in the `tests` folder will be used.
-// Small unit tests are generally included in the same file as the code they test.
-// You are free to add more tests here if you wish.
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Parses an action of the side to move written by `to_notation`. While a removal is
+    /// pending, a single coordinate is a removal, otherwise a placement. The `x` after a
+    /// mill is optional. The action is not checked for legality.
+    pub fn from_notation(&self, s: &str) -> Result<Action, LoadError> {
+        let s = s.trim();
+        let s = s.strip_suffix('x').unwrap_or(s);
+        let point =
+            |coord: &str| coord_to_point(coord).ok_or(LoadError::Parse("Invalid coordinate"));
+        let action = match s.split_once('-') {
+            Some((from, to)) => ActionKind::Move(point(from)?, point(to)?),
+            None if self.must_remove.is_some() => ActionKind::Remove(point(s)?),
+            None => ActionKind::Place(point(s)?),
+        };
+        Ok(Action {
+            player: self.to_move,
+            action,
+        })
+    }
 
-    #[test]
-    fn test_board_new_is_empty() {
-        let game = Game::new();
-        for pos in *game.points() {
-            assert_eq!(pos, None);
+    /// Applies `actions` in order and returns how many were applied.
+    /// On the first failure, returns its index and error; the game is left as it was just
+    /// before the failing action.
+    pub fn apply_all(&mut self, actions: &[Action]) -> Result<usize, (usize, ActionError)> {
+        for (i, &action) in actions.iter().enumerate() {
+            self.action(action).map_err(|err| (i, err))?;
         }
+        Ok(actions.len())
+    }
+
+    /// Returns the actions applied so far, oldest first. Removals after a mill are listed as
+    /// actions of their own. Undone actions are not included.
+    pub fn move_log(&self) -> &[Action] {
+        &self.moves
+    }
+
+    /// Returns the point and color of the piece taken by the last action, or `None` if the
+    /// last action was not a removal. Undoing the removal clears it again.
+    pub fn last_removed(&self) -> Option<(Point, Piece)> {
+        match *self.moves.last()? {
+            Action {
+                player,
+                action: ActionKind::Remove(point),
+            } => Some((point, player.opposite())),
+            _ => None,
+        }
+    }
+
+    /// Returns the actions applied so far, one per line, in the notation of `Action`'s `Display`
+    /// (e.g. `W P 0`). Undone actions are not included.
+    pub fn transcript(&self) -> String {
+        self.moves
+            .iter()
+            .map(|action| format!("{action}\n"))
+            .collect()
+    }
+
+    /// Plays a transcript as produced by `transcript()` from the start of a standard game.
+    /// Empty lines are skipped. Fails on the first line that cannot be parsed or applied.
+    pub fn replay(transcript: &str) -> Result<Game, LoadError> {
+        let mut game = Game::new();
+        for line in transcript.lines().filter(|line| !line.trim().is_empty()) {
+            game.action(line.parse().map_err(LoadError::Parse)?)?;
+        }
+        Ok(game)
+    }
+
+    /// Writes a record of the game for archiving: a header block with the players, date and
+    /// result from `metadata`, an empty line and the numbered actions, one per line. For example:
+    ///
+    /// ```text
+    /// [White "Alice"]
+    /// [Black "Bob"]
+    /// [Date "2024-05-01"]
+    /// [Result "1-0"]
+    ///
+    /// 1. W P 0
+    /// 2. B P 3
+    /// ```
+    ///
+    /// Results are `1-0`, `0-1`, `1/2-1/2 <reason>` (`repetition`, `no-progress` or
+    /// `stalemate`) and `*` for an unfinished game. Backslashes, quotes and line breaks in the
+    /// headers are escaped with a backslash (`\n`, `\r`). Like `transcript()`, the record
+    /// assumes the game started from the empty standard board.
+    pub fn to_record(&self, metadata: &Metadata) -> String {
+        let escape = |name: &str| {
+            name.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+        };
+        let mut record = format!(
+            "[White \"{}\"]\n[Black \"{}\"]\n[Date \"{}\"]\n[Result \"{}\"]\n\n",
+            escape(&metadata.white),
+            escape(&metadata.black),
+            escape(&metadata.date),
+            Game::result_text(metadata.result),
+        );
+        for (i, action) in self.moves.iter().enumerate() {
+            record.push_str(&format!("{}. {action}\n", i + 1));
+        }
+        record
+    }
+
+    /// Like `to_record`, dated with the current UTC date.
+    pub fn to_record_today(&self, white: &str, black: &str, result: GameResult) -> String {
+        self.to_record(&Metadata {
+            white: white.to_string(),
+            black: black.to_string(),
+            date: today(),
+            result,
+        })
+    }
+
+    /// Reads a record written by `to_record` and replays its actions.
+    /// Unknown headers are ignored; the four headers of `to_record` are required.
+    /// If the replayed game is over, the `Result` header must be its `result()`. Any other
+    /// result, e.g. `*` or a win by resignation, is only accepted for a game that is not over.
+    pub fn from_record(text: &str) -> Result<(Game, Metadata), LoadError> {
+        let mut lines = text.lines().map(str::trim);
+        let (mut white, mut black, mut date, mut result) = (None, None, None, None);
+        for line in lines.by_ref().take_while(|line| !line.is_empty()) {
+            let (key, value) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix("\"]"))
+                .and_then(|line| line.split_once(" \""))
+                .ok_or(LoadError::Parse("Invalid record header"))?;
+            let mut chars = value.chars();
+            let mut value = String::new();
+            while let Some(c) = chars.next() {
+                value.extend(match c {
+                    '\\' => chars.next().map(|c| match c {
+                        'n' => '\n',
+                        'r' => '\r',
+                        c => c,
+                    }),
+                    c => Some(c),
+                });
+            }
+            match key {
+                "White" => white = Some(value),
+                "Black" => black = Some(value),
+                "Date" => date = Some(value),
+                "Result" => result = Some(Game::parse_result(&value).map_err(LoadError::Parse)?),
+                _ => {}
+            }
+        }
+        let missing = LoadError::Parse("Missing record header");
+        let metadata = Metadata {
+            white: white.ok_or(missing)?,
+            black: black.ok_or(missing)?,
+            date: date.ok_or(missing)?,
+            result: result.ok_or(missing)?,
+        };
+
+        let mut game = Game::new();
+        for (i, line) in lines.filter(|line| !line.is_empty()).enumerate() {
+            let invalid = LoadError::Parse("Invalid record move");
+            let (number, action) = line.split_once(". ").ok_or(invalid)?;
+            if number.parse() != Ok(i + 1) {
+                return Err(invalid);
+            }
+            game.action(action.parse().map_err(LoadError::Parse)?)?;
+        }
+        if game.is_game_over() && game.result() != metadata.result {
+            return Err(LoadError::ResultMismatch);
+        }
+        Ok((game, metadata))
+    }
+
+    fn result_text(result: GameResult) -> &'static str {
+        match result {
+            GameResult::Ongoing => "*",
+            GameResult::Win(Player::White) => "1-0",
+            GameResult::Win(Player::Black) => "0-1",
+            GameResult::Draw(DrawReason::Repetition) => "1/2-1/2 repetition",
+            GameResult::Draw(DrawReason::NoProgress) => "1/2-1/2 no-progress",
+            GameResult::Draw(DrawReason::Stalemate) => "1/2-1/2 stalemate",
+        }
+    }
+
+    fn parse_result(text: &str) -> Result<GameResult, &'static str> {
+        [
+            GameResult::Ongoing,
+            GameResult::Win(Player::White),
+            GameResult::Win(Player::Black),
+            GameResult::Draw(DrawReason::Repetition),
+            GameResult::Draw(DrawReason::NoProgress),
+            GameResult::Draw(DrawReason::Stalemate),
+        ]
+        .into_iter()
+        .find(|&result| Game::result_text(result) == text)
+        .ok_or("Invalid record result")
+    }
+
+    /// Encodes the game compactly: the rules, the position before the first logged action
+    /// and the move log, two bytes per action. `from_bytes` reverses this.
+    ///
+    /// Layout (multi-byte numbers are little endian):
+    /// - `NMM` and the format version `1`
+    /// - rules: variant, flags (flying, stalemate draw, Black first, two bits for the mill
+    ///   removal policy, no immediate remill), loss threshold
+    /// - start position: White and Black bitboards (`u32`), flags (Black to move, removal
+    ///   pending for White/Black), unplaced and removed counts, the last mill (`255` if none)
+    ///   and the quiet move count (`u32`)
+    /// - the number of actions (`u32`) followed by the actions
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let start = self
+            .history
+            .first()
+            .cloned()
+            .unwrap_or_else(|| self.snapshot());
+        let config = self.config;
+        let mut data = b"NMM\x01".to_vec();
+        data.push(config.variant as u8);
+        data.push(
+            config.flying_enabled as u8
+                | (config.stalemate_is_draw as u8) << 1
+                | ((config.first_player == Player::Black) as u8) << 2
+                | (config.mill_removal_policy as u8) << 3
+                | (config.forbid_immediate_remill as u8) << 5,
+        );
+        data.push(config.loss_threshold);
+
+        data.extend(start.bits[0].to_le_bytes());
+        data.extend(start.bits[1].to_le_bytes());
+        data.push(
+            (start.to_move == Player::Black) as u8
+                | ((start.must_remove == Some(Player::White)) as u8) << 1
+                | ((start.must_remove == Some(Player::Black)) as u8) << 2,
+        );
+        data.extend(start.unplaced);
+        data.extend(start.removed);
+        data.extend(
+            start
+                .last_mill
+                .map_or([255; 3], |mill| mill.map(|p| p as u8)),
+        );
+        data.extend(start.quiet_moves.to_le_bytes());
+
+        data.extend((self.moves.len() as u32).to_le_bytes());
+        for action in &self.moves {
+            let player = ((action.player == Player::Black) as u8) << 7;
+            data.extend(match action.action {
+                ActionKind::Place(p) => [player | p as u8, 0],
+                ActionKind::Move(from, to) => [player | 1 << 5 | from as u8, to as u8],
+                ActionKind::Remove(p) => [player | 2 << 5 | p as u8, 0],
+            });
+        }
+        data
+    }
+
+    /// Decodes a game written by `to_bytes`. The start position is validated and the
+    /// actions are replayed, so corrupt data is rejected with an error.
+    pub fn from_bytes(data: &[u8]) -> Result<Game, LoadError> {
+        let (position, actions) = Game::decode_bytes(data).map_err(LoadError::Parse)?;
+        let mut game = Game::from_position(position).map_err(LoadError::Position)?;
+        game.apply_all(&actions).map_err(|(_, err)| err)?;
+        Ok(game)
+    }
+
+    // the start position and the actions of `from_bytes`, neither of them checked yet
+    fn decode_bytes(data: &[u8]) -> Result<(Position, Vec<Action>), &'static str> {
+        let mut data = data;
+        let mut take = |n: usize| -> Result<&[u8], &'static str> {
+            let (head, tail) = data.split_at_checked(n).ok_or("Truncated data")?;
+            data = tail;
+            Ok(head)
+        };
+        if take(4)? != b"NMM\x01" {
+            return Err("Not a game in binary format");
+        }
+        let rules = take(3)?;
+        let variant = match rules[0] {
+            0 => Variant::Three,
+            1 => Variant::Six,
+            2 => Variant::Nine,
+            3 => Variant::Twelve,
+            _ => return Err("Invalid variant"),
+        };
+        if rules[1] >> 6 != 0 {
+            return Err("Invalid rule flags");
+        }
+        let mill_removal_policy = match rules[1] >> 3 & 3 {
+            0 => MillRemovalPolicy::Standard,
+            1 => MillRemovalPolicy::AlwaysAllowed,
+            2 => MillRemovalPolicy::NeverFromMill,
+            _ => return Err("Invalid removal policy"),
+        };
+        let color = |black: bool| if black { Player::Black } else { Player::White };
+        let config = GameConfig {
+            variant,
+            flying_enabled: rules[1] & 1 != 0,
+            stalemate_is_draw: rules[1] & 2 != 0,
+            first_player: color(rules[1] & 4 != 0),
+            loss_threshold: rules[2],
+            mill_removal_policy,
+            forbid_immediate_remill: rules[1] & 32 != 0,
+        };
+
+        let u32_at = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+        let bits = [u32_at(take(4)?), u32_at(take(4)?)];
+        if bits[0] & bits[1] != 0 || (bits[0] | bits[1]) >> 24 != 0 {
+            return Err("Invalid board");
+        }
+        let board = std::array::from_fn(|p| {
+            if bits[0] & 1 << p != 0 {
+                Some(Color::White)
+            } else if bits[1] & 1 << p != 0 {
+                Some(Color::Black)
+            } else {
+                None
+            }
+        });
+        let flags = take(1)?[0];
+        let must_remove = match flags >> 1 {
+            0 => None,
+            1 => Some(Player::White),
+            2 => Some(Player::Black),
+            _ => return Err("Invalid removal flag"),
+        };
+        let counts = take(4)?;
+        let last_mill = match take(3)? {
+            [255, 255, 255] => None,
+            mill if mill.iter().all(|&p| p < 24) => Some([0, 1, 2].map(|i| mill[i] as Point)),
+            _ => return Err("Invalid mill"),
+        };
+        let position = Position {
+            board,
+            to_move: color(flags & 1 != 0),
+            unplaced: [counts[0], counts[1]],
+            removed: [counts[2], counts[3]],
+            must_remove,
+            last_mill,
+            quiet_moves: u32_at(take(4)?),
+            config,
+        };
+
+        let count = u32_at(take(4)?) as usize;
+        let mut actions = Vec::new();
+        for _ in 0..count {
+            let bytes = take(2)?;
+            let point = (bytes[0] & 0x1f) as Point;
+            let action = match bytes[0] >> 5 & 0b11 {
+                0 => ActionKind::Place(point),
+                1 => ActionKind::Move(point, bytes[1] as Point),
+                2 => ActionKind::Remove(point),
+                _ => return Err("Invalid action"),
+            };
+            actions.push(Action {
+                player: color(bytes[0] & 0x80 != 0),
+                action,
+            });
+        }
+        if !data.is_empty() {
+            return Err("Trailing data");
+        }
+        Ok((position, actions))
+    }
+
+    /// Returns true if `action` would be accepted by `action()`.
+    /// The game is not modified.
+    pub fn is_legal(&self, action: Action) -> bool {
+        self.check_action(action).is_ok()
+    }
+
+    // All rule checks for an action. `action()` only mutates state after this succeeded.
+    fn check_action(&self, action: Action) -> Result<(), ActionError> {
+        if self.resigned.is_some() {
+            return Err(ActionError::GameResigned);
+        }
+        // once noktalar gecerli mi diye bakalim
+        let check_point = |p: Point| -> Result<(), ActionError> {
+            if p >= self.config.variant.point_count() {
+                Err(ActionError::PointOutOfRange)
+            } else {
+                Ok(())
+            }
+        };
+
+        // eger birinin tas sokmesi gerekiyorsa
+        if let Some(waiting) = self.must_remove {
+            // bu hamle remove olmali ve yapan da o olmali
+            if action.player != waiting {
+                return Err(ActionError::RemovalPending);
+            }
+            return match action.action {
+                ActionKind::Remove(p) => {
+                    check_point(p)?;
+                    if self.board[p] != Some(action.player.opposite()) {
+                        return Err(ActionError::NotOpponentPiece);
+                    }
+                    // eger rakibin mill disi tasi varsa milldekini sokemez
+                    if !self.is_removable(p) {
+                        return Err(ActionError::PieceInMill);
+                    }
+                    Ok(())
+                }
+                _ => Err(ActionError::MustRemove),
+            };
+        }
+
+        // normal sıra kontrolü
+        if action.player != self.to_move {
+            return Err(ActionError::NotYourTurn);
+        }
+
+        let idx = Game::color_idx(action.player);
+        match action.action {
+            ActionKind::Place(p) => {
+                check_point(p)?;
+                if self.unplaced[idx] == 0 {
+                    return Err(ActionError::NoPiecesToPlace);
+                }
+                if self.board[p].is_some() {
+                    return Err(ActionError::Occupied);
+                }
+                Ok(())
+            }
+            ActionKind::Move(from, to) => {
+                check_point(from)?;
+                check_point(to)?;
+                if from == to {
+                    return Err(ActionError::SamePoint);
+                }
+
+                let phase = self.phase(action.player);
+                if phase == Phase::Placement {
+                    return Err(ActionError::StillPlacing);
+                }
+                if self.board[from] != Some(action.player) {
+                    return Err(ActionError::NoPieceAtSource);
+                }
+                if self.board[to].is_some() {
+                    return Err(ActionError::DestinationOccupied);
+                }
+                if phase != Phase::Flying && !self.config.variant.are_adjacent(from, to) {
+                    return Err(ActionError::NotAdjacent);
+                }
+                if self.recloses_mill(action) {
+                    return Err(ActionError::ImmediateRemill);
+                }
+                Ok(())
+            }
+            ActionKind::Remove(_) => Err(ActionError::RemoveNotAllowed),
+        }
+    }
+
+    // Called after `player` put a piece on `point`, either by placing or moving.
+    // A closed mill obliges the player to remove a piece, if any can be removed at all.
+    // If none can, the turn passes without a removal (see `mill_without_removal`).
+    // Closing two mills at once still allows only a single removal.
+    fn end_turn(&mut self, point: Point, player: Player) {
+        let opponent = player.opposite();
+        self.last_mill = self.closed_mill_at(point, player);
+        if self.last_mill.is_some() {
+            // Check if player can actually remove any piece
+            let can_remove =
+                (0..24).any(|i| self.board[i] == Some(opponent) && self.is_removable(i));
+            if can_remove {
+                self.must_remove = Some(player);
+                return;
+            }
+            // Can't remove, so continue the game
+        }
+        self.to_move = opponent;
+    }
+
+    // A piece may be taken if it is not in a mill. Whether pieces in mills may be taken
+    // depends on the mill removal policy.
+    fn is_removable(&self, point: Point) -> bool {
+        let Some(color) = self.board.get(point).copied().flatten() else {
+            return false;
+        };
+        match self.config.mill_removal_policy {
+            MillRemovalPolicy::Standard => {
+                !self.is_mill_point(point) || self.all_pieces_in_mills(color)
+            }
+            MillRemovalPolicy::AlwaysAllowed => true,
+            MillRemovalPolicy::NeverFromMill => !self.is_mill_point(point),
+        }
+    }
+
+    fn piece_char(piece: Option<Piece>) -> char {
+        match piece {
+            Some(Color::White) => 'W',
+            Some(Color::Black) => 'B',
+            None => '.',
+        }
+    }
+
+    fn piece_from_char(c: char) -> Option<Option<Piece>> {
+        match c {
+            'W' => Some(Some(Color::White)),
+            'B' => Some(Some(Color::Black)),
+            '.' => Some(None),
+            _ => None,
+        }
+    }
+
+    fn color_idx(c: Color) -> usize {
+        match c {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board,
+            bits: self.bits,
+            mills: self.mills,
+            board_hash: self.board_hash,
+            to_move: self.to_move,
+            unplaced: self.unplaced,
+            removed: self.removed,
+            must_remove: self.must_remove,
+            last_mill: self.last_mill,
+            quiet_moves: self.quiet_moves,
+        }
+    }
+
+    fn restore(&mut self, snap: Snapshot) {
+        self.board = snap.board;
+        self.bits = snap.bits;
+        self.mills = snap.mills;
+        self.board_hash = snap.board_hash;
+        self.to_move = snap.to_move;
+        self.unplaced = snap.unplaced;
+        self.removed = snap.removed;
+        self.must_remove = snap.must_remove;
+        self.last_mill = snap.last_mill;
+        self.quiet_moves = snap.quiet_moves;
+    }
+
+    // the first mill of the variant through `point` that is fully occupied by `color`
+    fn closed_mill_at(&self, point: Point, color: Color) -> Option<[Point; 3]> {
+        if point >= 24 {
+            return None;
+        }
+        let bits = self.bits[Self::color_idx(color)];
+        let variant = self.config.variant;
+        let masks = variant.mill_masks();
+        match variant {
+            // only the two standard mills through `point` can be closed (Six uses the first 8)
+            Variant::Six | Variant::Nine => Self::POINT_MILLS[point]
+                .into_iter()
+                .find(|&i| i < masks.len() && bits & masks[i] == masks[i])
+                .map(|i| Self::MILLS[i]),
+            Variant::Three | Variant::Twelve => variant
+                .mills()
+                .iter()
+                .zip(masks)
+                .find(|&(_, &mask)| mask & (1 << point) != 0 && bits & mask == mask)
+                .map(|(mill, _)| *mill),
+        }
+    }
+
+    // whether the placement or move would close a mill, without applying it
+    fn closes_mill(&self, action: Action) -> bool {
+        let own = self.bits[Self::color_idx(action.player)];
+        let (bits, to) = match action.action {
+            ActionKind::Place(p) => (own | 1 << p, p),
+            ActionKind::Move(from, to) => (own & !(1 << from) | 1 << to, to),
+            ActionKind::Remove(_) => return false,
+        };
+        self.config
+            .variant
+            .mill_masks()
+            .iter()
+            .any(|&mask| mask & (1 << to) != 0 && bits & mask == mask)
+    }
+
+    // True if the rules forbid immediate remills and `action` is a move that closes a mill
+    // which the same player's previous move opened.
+    fn recloses_mill(&self, action: Action) -> bool {
+        let ActionKind::Move(from, to) = action.action else {
+            return false;
+        };
+        if !self.config.forbid_immediate_remill {
+            return false;
+        }
+        let player = action.player;
+        let idx = Self::color_idx(player);
+        let Some(previous) = self
+            .moves
+            .iter()
+            .rposition(|a| a.player == player && !matches!(a.action, ActionKind::Remove(_)))
+        else {
+            return false;
+        };
+        let ActionKind::Move(opened_from, _) = self.moves[previous].action else {
+            return false;
+        };
+        let before = self.history[previous].bits[idx];
+        let after = self.bits[idx] & !(1 << from) | 1 << to;
+        self.config.variant.mill_masks().iter().any(|&mask| {
+            mask & 1 << opened_from != 0
+                && before & mask == mask
+                && mask & 1 << to != 0
+                && after & mask == mask
+        })
+    }
+
+    // true if `player` has placed all pieces and fewer than three are left on the board
+    fn too_few_pieces(&self, player: Player) -> bool {
+        self.remaining_unplaced(player) == 0 && self.count_pieces(player) < 3
+    }
+
+    fn all_pieces_in_mills(&self, color: Color) -> bool {
+        self.bits[Self::color_idx(color)] & !self.mill_points() == 0
+    }
+
+    /// Returns how many pieces of `color` are on the board.
+    /// Pieces that are still to be placed are not counted.
+    pub fn count_pieces(&self, color: Color) -> u8 {
+        self.bits[Self::color_idx(color)].count_ones() as u8
+    }
+
+    fn empty_points(&self) -> impl Iterator<Item = Point> + '_ {
+        let empty = self.empty_bits();
+        (0..self.config.variant.point_count()).filter(move |&p| empty & (1 << p) != 0)
+    }
+
+    // the empty points of the variant's board as a bitboard
+    fn empty_bits(&self) -> u32 {
+        let board = (1 << self.config.variant.point_count()) - 1;
+        board & !(self.bits[0] | self.bits[1])
+    }
+
+    // all board changes go through here to keep `bits` in sync
+    fn set_point(&mut self, point: Point, piece: Option<Piece>) {
+        for color in [self.board[point], piece].into_iter().flatten() {
+            self.board_hash ^= ZOBRIST_KEYS[point * 2 + Self::color_idx(color)];
+        }
+        self.bits[0] &= !(1 << point);
+        self.bits[1] &= !(1 << point);
+        if let Some(color) = piece {
+            self.bits[Self::color_idx(color)] |= 1 << point;
+        }
+        self.board[point] = piece;
+        for (i, &mask) in self.config.variant.mill_masks().iter().enumerate() {
+            if mask & 1 << point != 0 {
+                if self.bits[0] & mask == mask || self.bits[1] & mask == mask {
+                    self.mills |= 1 << i;
+                } else {
+                    self.mills &= !(1 << i);
+                }
+            }
+        }
+    }
+
+    // the points of all closed mills
+    fn mill_points(&self) -> u32 {
+        let masks = self.config.variant.mill_masks();
+        (0..masks.len())
+            .filter(|&i| self.mills & 1 << i != 0)
+            .fold(0, |points, i| points | masks[i])
+    }
+
+    // draws the layout of `Display` with `c(point)` for every point
+    fn write_board<D: Display>(
+        &self,
+        f: &mut impl std::fmt::Write,
+        c: impl Fn(Point) -> D,
+    ) -> std::fmt::Result {
+        if self.config.variant == Variant::Three {
+            writeln!(f, "{}-{}-{}", c(0), c(1), c(2))?;
+            writeln!(f, "|\\|/|")?;
+            writeln!(f, "{}-{}-{}", c(7), c(8), c(3))?;
+            writeln!(f, "|/|\\|")?;
+            return write!(f, "{}-{}-{}", c(6), c(5), c(4));
+        }
+        writeln!(f, "{}-----{}-----{}", c(0), c(1), c(2))?;
+        writeln!(f, "| {}---{}---{} |", c(8), c(9), c(10))?;
+        if self.config.variant == Variant::Six {
+            writeln!(f, "| |       | |")?;
+            writeln!(f, "{}-{}       {}-{}", c(7), c(15), c(11), c(3))?;
+            writeln!(f, "| |       | |")?;
+        } else {
+            writeln!(f, "| | {}-{}-{} | |", c(16), c(17), c(18))?;
+            writeln!(
+                f,
+                "{}-{}-{}   {}-{}-{}",
+                c(7),
+                c(15),
+                c(23),
+                c(19),
+                c(11),
+                c(3)
+            )?;
+            writeln!(f, "| | {}-{}-{} | |", c(22), c(21), c(20))?;
+        }
+        writeln!(f, "| {}---{}---{} |", c(14), c(13), c(12))?;
+        write!(f, "{}-----{}-----{}", c(6), c(5), c(4))
+    }
+
+    fn set_board(&mut self, board: [Option<Piece>; 24]) {
+        for (point, piece) in board.into_iter().enumerate() {
+            self.set_point(point, piece);
+        }
+    }
+
+    // oyuncunun şu anda YASAL hamlesi var mı?
+    fn player_can_move(&self, player: Player) -> bool {
+        // yerleştirme ya da uçma: herhangi bir boş yer yeterli
+        let empty = self.empty_bits();
+        if self.phase(player) != Phase::Movement {
+            return empty != 0;
+        }
+
+        // normal hareket: komsusuna gidebilmeli
+        let own = self.bits[Self::color_idx(player)];
+        let adjacency = self.config.variant.adjacency();
+        (0..24).any(|from| own & (1 << from) != 0 && adjacency[from] & empty != 0)
+    }
+}
+
+impl NmmGame for Game {
+    fn new() -> Self {
+        Game::with_variant(Variant::Nine)
+    }
+
+    fn action(&mut self, action: Action) -> Result<(), ActionError> {
+        // All checks happen before anything changes, so a rejected action leaves the game,
+        // including its history, untouched.
+        self.check_action(action)?;
+
+        // snapshot
+        self.history.push(self.snapshot());
+        self.moves.push(action);
+        self.redo_stack.clear();
+
+        let idx = Game::color_idx(action.player);
+        match action.action {
+            ActionKind::Place(p) => {
+                self.set_point(p, Some(action.player));
+                self.unplaced[idx] -= 1;
+                self.end_turn(p, action.player);
+            }
+            ActionKind::Move(from, to) => {
+                self.set_point(from, None);
+                self.set_point(to, Some(action.player));
+                self.end_turn(to, action.player);
+            }
+            ActionKind::Remove(p) => {
+                let opponent = action.player.opposite();
+                self.set_point(p, None);
+                self.captures[idx].push(p);
+                self.removed[Game::color_idx(opponent)] += 1;
+                self.must_remove = None;
+                self.last_mill = None;
+                self.to_move = opponent;
+            }
+        }
+
+        // only moves that close no mill count towards the no-progress rule
+        self.quiet_moves = match action.action {
+            ActionKind::Move(..) if self.last_mill.is_none() => self.quiet_moves + 1,
+            _ => 0,
+        };
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), ActionError> {
+        if self.resigned.take().is_some() {
+            Ok(())
+        } else if let Some(snap) = self.history.pop() {
+            let action = self.moves.pop().expect("one action per snapshot");
+            if let ActionKind::Remove(_) = action.action {
+                self.captures[Game::color_idx(action.player)].pop();
+            }
+            self.redo_stack.push((self.snapshot(), action));
+            self.restore(snap);
+            Ok(())
+        } else {
+            Err(ActionError::NothingToUndo)
+        }
+    }
+
+    fn points(&self) -> &[Option<Piece>; 24] {
+        &self.board
+    }
+
+    fn winner(&self) -> Option<Player> {
+        match self.result() {
+            GameResult::Win(player) => Some(player),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Game {
+    /// A standard game with an empty board, like `Game::new()`.
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+impl Display for Game {
+    /// Draws the board in the layout of the `Point` documentation,
+    /// with `W` and `B` for pieces and `.` for empty points.
+    /// Diagonal lines of Twelve Men's Morris are not drawn.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_board(f, |p| Game::piece_char(self.board[p]))
+    }
+}
+
+impl FromStr for Game {
+    type Err = LoadError;
+
+    /// Parses a Nine Men's Morris position written as 24 characters in point order (`W`, `B` or `.`),
+    /// optionally followed by the side to move (`W` or `B`, default `W`) and the number of
+    /// unplaced White and Black pieces (default `0 0`). Removed pieces are inferred, and the
+    /// position is checked like in `Game::from_position`.
+    ///
+    /// Example inputs:
+    /// "WW.B...................." - White to move, all pieces placed
+    /// "W..B.................... B 8 8" - Black to move during placement
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        if parts.is_empty() || parts.len() == 3 || parts.len() > 4 {
+            return Err(LoadError::Parse("Invalid position format"));
+        }
+
+        let mut board = Game::new();
+        board.set_board_string(parts[0])?;
+        let mut position = board.position();
+        if let Some(side) = parts.get(1) {
+            position.to_move = match *side {
+                "W" => Player::White,
+                "B" => Player::Black,
+                _ => return Err(LoadError::Parse("Invalid side to move")),
+            };
+        }
+        position.unplaced = [0, 0];
+        if parts.len() == 4 {
+            for idx in 0..2 {
+                position.unplaced[idx] = parts[2 + idx]
+                    .parse()
+                    .map_err(|_| LoadError::Parse("Invalid unplaced count"))?;
+            }
+        }
+        let pieces = position.config.variant.pieces();
+        for color in [Color::White, Color::Black] {
+            let idx = Game::color_idx(color);
+            let present = board
+                .count_pieces(color)
+                .saturating_add(position.unplaced[idx]);
+            if present > pieces {
+                return Err(LoadError::Position("Too many pieces"));
+            }
+            position.removed[idx] = pieces - present;
+        }
+        Game::from_position(position).map_err(LoadError::Position)
+    }
+}
+
+// For grading this assignment, the tests in the `tests` folder will be used.
+// Small unit tests are generally included in the same file as the code they test.
+// You are free to add more tests here if you wish.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    fn play(game: &mut Game, actions: &[&str]) {
+        for action in actions {
+            let action: Action = action.parse().expect("parse failed");
+            game.action(action).expect("illegal action");
+        }
+    }
+
+    // Sets up a movement phase position with the given pieces on the board.
+    fn setup(white: &[Point], black: &[Point]) -> Game {
+        let mut game = Game::new();
+        for &p in white {
+            game.set_point(p, Some(Color::White));
+        }
+        for &p in black {
+            game.set_point(p, Some(Color::Black));
+        }
+        game.unplaced = [0, 0];
+        game.removed = [9 - white.len() as u8, 9 - black.len() as u8];
+        game
+    }
+
+    #[test]
+    fn test_default_game() {
+        let game = Game::default();
+        assert!(game.points().iter().all(|p| p.is_none()));
+        assert_eq!(game.to_move(), Player::White);
+        assert_eq!(game.position(), Game::new().position());
+    }
+
+    #[test]
+    fn test_board_new_is_empty() {
+        let game = Game::new();
+        for pos in *game.points() {
+            assert_eq!(pos, None);
+        }
+    }
+
+    #[test]
+    fn test_phase_transitions() {
+        let mut game = Game::new();
+        assert_eq!(game.phase(Player::White), Phase::Placement);
+        assert_eq!(game.phase(Player::Black), Phase::Placement);
+
+        // 9 placements each, no mills formed
+        play(
+            &mut game,
+            &[
+                "W P 0", "B P 1", "W P 2", "B P 3", "W P 4", "B P 5", "W P 6", "B P 7", "W P 8",
+                "B P 9", "W P 10", "B P 11", "W P 12", "B P 13", "W P 14", "B P 15", "W P 17",
+            ],
+        );
+        // White has placed all nine, Black still has one left
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert_eq!(game.phase(Player::Black), Phase::Placement);
+
+        play(&mut game, &["B P 16"]);
+        assert_eq!(game.phase(Player::Black), Phase::Movement);
+    }
+
+    #[test]
+    fn test_phase_flying() {
+        let mut game = Game::new();
+        game.unplaced = [0, 0];
+        game.set_point(0, Some(Color::White));
+        game.set_point(5, Some(Color::White));
+        game.set_point(9, Some(Color::White));
+        game.set_point(1, Some(Color::Black));
+        game.set_point(2, Some(Color::Black));
+        game.set_point(3, Some(Color::Black));
+        game.set_point(4, Some(Color::Black));
+        assert_eq!(game.phase(Player::White), Phase::Flying);
+        assert_eq!(game.phase(Player::Black), Phase::Movement);
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut game = setup(&[0, 9, 13, 20], &[4, 11, 22, 16]);
+        let shuffle = ["W M 0 1", "B M 4 3", "W M 1 0", "B M 3 4"];
+        assert_eq!(game.repetition_count(), 1);
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 2);
+        assert!(!game.is_draw());
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        play(&mut game, &shuffle[..3]);
+        assert!(!game.is_draw());
+        assert_eq!(game.repetition_count(), 2);
+        play(&mut game, &shuffle[3..]);
+        assert_eq!(game.repetition_count(), 3);
+        assert!(game.is_draw());
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Repetition));
+        assert_eq!(game.winner(), None);
+
+        assert!(game.undo().is_ok());
+        assert!(!game.is_draw());
+    }
+
+    #[test]
+    fn test_result_win() {
+        let mut game = Game::new();
+        assert_eq!(game.result(), GameResult::Ongoing);
+        game.removed = [0, 7];
+        assert_eq!(game.result(), GameResult::Win(Player::White));
+        assert_eq!(game.winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn test_no_repetition_during_placement() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 1", "W P 2", "B P 3"]);
+        assert_eq!(game.repetition_count(), 1);
+        assert!(!game.is_draw());
+    }
+
+    #[test]
+    fn test_undo_place_mill_and_remove() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        let before = game.snapshot();
+        play(&mut game, &["W P 7"]);
+        assert_eq!(game.must_remove, Some(Player::White));
+
+        // failed removals must not leave anything behind in the history
+        assert!(game.action("W R 0".parse().unwrap()).is_err());
+        assert!(game.action("W R 5".parse().unwrap()).is_err());
+        play(&mut game, &["W R 4"]);
+
+        assert!(game.undo().is_ok());
+        assert_eq!(game.must_remove, Some(Player::White));
+        assert_eq!(game.board[4], Some(Color::Black));
+        assert!(game.undo().is_ok());
+        assert!(game.snapshot().same_position(&before));
+    }
+
+    #[test]
+    fn test_undo_move_mill_and_remove() {
+        let mut game = setup(&[0, 1, 3, 20], &[4, 5, 11, 22]);
+        let before = game.snapshot();
+        play(&mut game, &["W M 3 2"]);
+        assert_eq!(game.must_remove, Some(Player::White));
+        play(&mut game, &["W R 22"]);
+        assert_eq!(game.to_move, Player::Black);
+
+        assert!(game.undo().is_ok());
+        assert_eq!(game.must_remove, Some(Player::White));
+        assert_eq!(game.board[22], Some(Color::Black));
+        assert!(game.undo().is_ok());
+        assert!(game.snapshot().same_position(&before));
+        assert!(game.undo().is_err());
+    }
+
+    #[test]
+    fn test_neighbors_are_symmetric() {
+        let linked = |a: Point, b: Point| Game::NEIGHBORS[a].contains(&b);
+        for a in 0..24 {
+            for b in 0..24 {
+                assert_eq!(linked(a, b), linked(b, a), "{a} {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_legal_agrees_with_action() {
+        let mut game = Game::new();
+        let script = [
+            "W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4", "B P 5", "W P 15", "B P 13",
+            "W P 23", "W R 5", "B P 14", "W P 1", "B P 12", "B R 1",
+        ];
+        let probes = [
+            "W P 0", "B P 0", "W P 24", "W M 0 1", "B M 3 2", "W R 3", "B R 0", "B R 7", "W R 13",
+            "B R 6", "W P 9", "B P 9",
+        ];
+        for step in script {
+            for probe in probes {
+                let probe: Action = probe.parse().unwrap();
+                let legal = game.is_legal(probe);
+                let history_len = game.history.len();
+                let board = game.board;
+                let applied = game.action(probe).is_ok();
+                assert_eq!(legal, applied, "{probe}");
+                if applied {
+                    game.undo().unwrap();
+                }
+                assert_eq!(game.history.len(), history_len);
+                assert_eq!(game.board, board);
+            }
+            play(&mut game, &[step]);
+        }
+    }
+
+    #[test]
+    fn test_color_parse_and_display() {
+        for s in ["W", "w", "White", "WHITE", "white"] {
+            assert_eq!(s.parse::<Color>(), Ok(Color::White));
+        }
+        for s in ["B", "b", "Black", "bLaCk"] {
+            assert_eq!(s.parse::<Color>(), Ok(Color::Black));
+        }
+        for s in ["", "X", "Wh", "white "] {
+            assert_eq!(s.parse::<Color>(), Err("Invalid player"));
+        }
+        for color in [Color::White, Color::Black] {
+            assert_eq!(color.to_string().parse::<Color>(), Ok(color));
+        }
+        assert_eq!(Color::White.to_string(), "W");
+
+        let action: Action = "black M 0 1".parse().unwrap();
+        assert_eq!(action.to_string(), "B M 0 1");
+        assert_eq!("X P 0".parse::<Action>().err(), Some("Invalid player"));
+    }
+
+    #[test]
+    fn test_action_ordering() {
+        let actions: Vec<Action> = [
+            "W R 3", "W M 1 2", "B P 7", "W P 5", "W M 0 9", "W P 5", "B R 0", "W M 0 1",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        let sorted: BTreeSet<Action> = actions.iter().copied().collect();
+        let sorted: Vec<String> = sorted.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "B P 7", "B R 0", "W P 5", "W M 0 1", "W M 0 9", "W M 1 2", "W R 3"
+            ]
+        );
+
+        let unique: HashSet<Action> = actions.into_iter().collect();
+        assert_eq!(unique.len(), 7);
+    }
+
+    #[test]
+    fn test_display_empty_board() {
+        let game = Game::new();
+        let expected = "\
+.-----.-----.
+| .---.---. |
+| | .-.-. | |
+.-.-.   .-.-.
+| | .-.-. | |
+| .---.---. |
+.-----.-----.";
+        assert_eq!(game.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_with_pieces() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 9", "W P 19", "B P 4", "W P 22"]);
+        let expected = "\
+W-----.-----.
+| .---B---. |
+| | .-.-. | |
+.-.-.   W-.-.
+| | W-.-. | |
+| .---.---. |
+.-----.-----B";
+        assert_eq!(game.to_string(), expected);
+    }
+
+    #[test]
+    fn test_render_unicode() {
+        let expected = "\
+┌─────┬─────┐
+│ ┌───┼───┐ │
+│ │ ┌─┴─┐ │ │
+├─┼─┤   ├─┼─┤
+│ │ └─┬─┘ │ │
+│ └───┼───┘ │
+└─────┴─────┘";
+        assert_eq!(Game::new().render_unicode(), expected);
+
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 9", "W P 19", "B P 4", "W P 22"]);
+        let expected = "\
+○─────┬─────┐
+│ ┌───●───┐ │
+│ │ ┌─┴─┐ │ │
+├─┼─┤   ○─┼─┤
+│ │ ○─┬─┘ │ │
+│ └───┼───┘ │
+└─────┴─────●";
+        assert_eq!(game.render_unicode(), expected);
+        assert!(expected.lines().all(|line| line.chars().count() == 13));
+
+        let mut game = Game::with_variant(Variant::Three);
+        play(&mut game, &["W P 8", "B P 0"]);
+        assert_eq!(game.render_unicode(), "●─┬─┐\n│╲│╱│\n├─○─┤\n│╱│╲│\n└─┴─┘");
+
+        let game = Game::with_variant(Variant::Six);
+        assert_eq!(game.render_unicode().lines().nth(3), Some("├─┤       ├─┤"));
+
+        let mut game = Game::with_variant(Variant::Twelve);
+        play(&mut game, &["W P 0", "B P 8", "W P 19", "B P 4", "W P 22"]);
+        let expected = "\
+○─────┬─────┐
+│╲    │    ╱│
+│ ●───┼───┐ │
+│ │╲  │  ╱│ │
+│ │ ┌─┴─┐ │ │
+├─┼─┤   ○─┼─┤
+│ │ ○─┬─┘ │ │
+│ │╱  │  ╲│ │
+│ └───┼───┘ │
+│╱    │    ╲│
+└─────┴─────●";
+        assert_eq!(game.render_unicode(), expected);
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3"]);
+        let board = game.render_ansi();
+        assert!(board.starts_with("\x1b[97mW\x1b[0m-----.-----.\n"));
+        assert!(board.contains("\x1b[91mB\x1b[0m"));
+        assert_eq!(game.render_colored(false), game.to_string());
+
+        // White's mill 0-1-2 is highlighted, Black's single piece is not
+        play(&mut game, &["W P 1", "B P 4", "W P 2"]);
+        let board = game.render_ansi();
+        assert_eq!(board.matches("\x1b[1;4;97mW\x1b[0m").count(), 3);
+        assert_eq!(board.matches("\x1b[91mB\x1b[0m").count(), 2);
+        assert_eq!(Game::new().render_ansi(), Game::new().to_string());
+    }
+
+    #[test]
+    fn test_board_string() {
+        let mut game = Game::new();
+        assert_eq!(game.board_string(), ".".repeat(24));
+        play(&mut game, &["W P 0", "B P 3", "W P 13"]);
+        assert_eq!(game.board_string(), "W..B.........W..........");
+
+        let mut copy = Game::new();
+        copy.set_board_string(&game.board_string()).unwrap();
+        assert_eq!(copy.points(), game.points());
+        assert_eq!(copy.count_pieces(Color::White), 2);
+        assert_eq!(copy.to_move(), Player::White);
+
+        assert_eq!(
+            copy.set_board_string("W..B"),
+            Err(LoadError::Parse("Board must have 24 points"))
+        );
+        assert_eq!(
+            copy.set_board_string(&"W".repeat(25)),
+            Err(LoadError::Parse("Board must have 24 points"))
+        );
+        assert_eq!(
+            copy.set_board_string("W..B.........X.........."),
+            Err(LoadError::Parse("Invalid point character"))
+        );
+        assert_eq!(copy.points(), game.points());
+    }
+
+    #[test]
+    fn test_zobrist_hash_undo() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        let hash = game.zobrist_hash();
+        play(&mut game, &["W P 7"]);
+        assert_ne!(game.zobrist_hash(), hash);
+        game.undo().unwrap();
+        assert_eq!(game.zobrist_hash(), hash);
+    }
+
+    #[test]
+    fn test_current_hash_matches_zobrist_hash() {
+        random_positions(|game| {
+            assert_eq!(game.current_hash(), game.zobrist_hash(), "{game}");
+            let mut undone = game.clone();
+            if undone.undo().is_ok() {
+                assert_eq!(undone.current_hash(), undone.zobrist_hash(), "{undone}");
+            }
+        });
+        let game: Game = "WW.B.B.B..............B. B 2 3".parse().unwrap();
+        assert_eq!(game.current_hash(), game.zobrist_hash());
+    }
+
+    #[test]
+    fn test_zobrist_hash_ignores_history() {
+        let mut a = setup(&[0, 9, 13, 20], &[4, 11, 22, 16]);
+        let b = setup(&[0, 9, 13, 20], &[4, 11, 22, 16]);
+        play(&mut a, &["W M 0 1", "B M 4 3", "W M 1 0", "B M 3 4"]);
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_ne!(Game::new().zobrist_hash(), b.zobrist_hash());
+    }
+
+    #[test]
+    fn test_symmetries_preserve_topology() {
+        for sym in &Game::SYMMETRIES {
+            let mut seen = [false; 24];
+            for &p in sym {
+                seen[p] = true;
+            }
+            assert!(seen.iter().all(|&s| s));
+            for mill in &Game::MILLS {
+                let mut image = mill.map(|p| sym[p]);
+                image.sort();
+                assert!(Game::MILLS.iter().any(|m| {
+                    let mut m = *m;
+                    m.sort();
+                    m == image
+                }));
+            }
+            for from in 0..24 {
+                for to in 0..24 {
+                    assert_eq!(
+                        Variant::Nine.are_adjacent(from, to),
+                        Variant::Nine.are_adjacent(sym[from], sym[to])
+                    );
+                }
+            }
+        }
+        assert_eq!(Game::SYMMETRIES[0], std::array::from_fn(|p| p));
+    }
+
+    #[test]
+    fn test_canonical_rotations() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 9", "W P 19", "B P 4", "W P 22"]);
+        let canonical = game.canonical();
+        for rotation in 1..4 {
+            let mut rotated = Game::new();
+            rotated.set_board(game.transformed(&Game::SYMMETRIES[rotation]));
+            assert_ne!(rotated.board, game.board);
+            assert_eq!(rotated.canonical(), canonical);
+        }
+
+        let mut other = Game::new();
+        play(&mut other, &["W P 0", "B P 9", "W P 19", "B P 4", "W P 21"]);
+        assert_ne!(other.canonical(), canonical);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        let json = serde_json::to_string(&game).unwrap();
+        let mut restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.points(), game.points());
+        assert_eq!(restored.winner(), game.winner());
+        assert_eq!(restored.must_remove, Some(Player::White));
+        assert_eq!(restored.unplaced, game.unplaced);
+        assert_eq!(restored.removed, game.removed);
+        assert_eq!(restored.history.len(), game.history.len());
+
+        game.undo().unwrap();
+        restored.undo().unwrap();
+        assert_eq!(restored.points(), game.points());
+        assert_eq!(restored.to_move, game.to_move);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        assert_eq!(Game::neighbors(0), vec![1, 7]);
+        assert_eq!(Game::neighbors(9), vec![1, 8, 10, 17]);
+        assert_eq!(Game::neighbors(16), vec![17, 23]);
+        assert!(Game::neighbors(24).is_empty());
+    }
+
+    #[test]
+    fn test_mills_containing() {
+        assert_eq!(Game::mills_containing(0), vec![[0, 1, 2], [6, 7, 0]]);
+        assert_eq!(Game::mills_containing(1), vec![[0, 1, 2], [1, 9, 17]]);
+        assert_eq!(Game::mills_containing(19), vec![[18, 19, 20], [3, 11, 19]]);
+        assert!(Game::mills_containing(24).is_empty());
+        assert!(Game::mills_containing(99).is_empty());
+    }
+
+    #[test]
+    fn test_last_mill() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        assert_eq!(game.last_mill(), None);
+        play(&mut game, &["W P 7"]);
+        assert_eq!(game.last_mill(), Some([6, 7, 0]));
+        play(&mut game, &["W R 4"]);
+        assert_eq!(game.last_mill(), None);
+        game.undo().unwrap();
+        assert_eq!(game.last_mill(), Some([6, 7, 0]));
+        game.undo().unwrap();
+        assert_eq!(game.last_mill(), None);
+    }
+
+    #[test]
+    fn test_last_mill_double() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "W P 0", "B P 3", "W P 2", "B P 5", "W P 9", "B P 22", "W P 17", "B P 20",
+            ],
+        );
+        // closes [0, 1, 2] and [1, 9, 17] at once
+        play(&mut game, &["W P 1"]);
+        assert_eq!(game.last_mill(), Some([0, 1, 2]));
+    }
+
+    #[test]
+    fn test_piece_counters() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        assert_eq!(game.remaining_unplaced(Player::White), 6);
+        assert_eq!(game.remaining_unplaced(Player::Black), 7);
+        assert_eq!(game.removed_count(Player::White), 0);
+        assert_eq!(game.removed_count(Player::Black), 1);
+    }
+
+    #[test]
+    fn test_to_move_and_must_remove() {
+        let mut game = Game::new();
+        assert_eq!(game.to_move(), Player::White);
+        assert_eq!(game.must_remove(), None);
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        assert_eq!(game.to_move(), Player::White);
+        play(&mut game, &["W P 7"]);
+        assert_eq!(game.must_remove(), Some(Player::White));
+        assert_eq!(game.to_move(), Player::White);
+        play(&mut game, &["W R 4"]);
+        assert_eq!(game.must_remove(), None);
+        assert_eq!(game.to_move(), Player::Black);
+    }
+
+    #[test]
+    fn test_double_mill_allows_single_removal() {
+        // A move always vacates a point on one of the destination's mills, so only a
+        // placement can close two mills at once.
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "W P 0", "B P 3", "W P 2", "B P 5", "W P 9", "B P 22", "W P 17", "B P 20",
+            ],
+        );
+        play(&mut game, &["W P 1"]);
+        assert_eq!(game.must_remove(), Some(Player::White));
+        play(&mut game, &["W R 3"]);
+        assert_eq!(game.must_remove(), None);
+        assert_eq!(game.to_move(), Player::Black);
+        assert!(game.action("W R 5".parse().unwrap()).is_err());
+        assert_eq!(game.removed_count(Player::Black), 1);
+    }
+
+    #[test]
+    fn test_parse_game() {
+        let game: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert_eq!(game.points()[0], Some(Color::White));
+        assert_eq!(game.points()[1], Some(Color::White));
+        assert_eq!(game.points()[3], Some(Color::Black));
+        assert_eq!(game.points()[22], Some(Color::Black));
+        assert_eq!(game.to_move(), Player::Black);
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert_eq!(game.removed_count(Player::White), 7);
+        assert_eq!(game.winner(), Some(Player::Black));
+    }
+
+    #[test]
+    fn test_parse_game_round_trip() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4", "B P 9",
+            ],
+        );
+        let board: String = game.points().iter().map(|&p| Game::piece_char(p)).collect();
+        let text = format!("{board} W 6 6");
+        let parsed: Game = text.parse().unwrap();
+        assert_eq!(parsed.points(), game.points());
+        assert_eq!(parsed.to_move(), game.to_move());
+        assert_eq!(parsed.unplaced, game.unplaced);
+        assert_eq!(parsed.removed, game.removed);
+        assert!(parsed.snapshot().same_position(&game.snapshot()));
+    }
+
+    #[test]
+    fn test_parse_game_errors() {
+        assert_eq!(
+            "".parse::<Game>().err(),
+            Some(LoadError::Parse("Invalid position format"))
+        );
+        assert_eq!(
+            "W.B".parse::<Game>().err(),
+            Some(LoadError::Parse("Board must have 24 points"))
+        );
+        assert_eq!(
+            "X.......................".parse::<Game>().err(),
+            Some(LoadError::Parse("Invalid point character"))
+        );
+        assert_eq!(
+            "........................ X".parse::<Game>().err(),
+            Some(LoadError::Parse("Invalid side to move"))
+        );
+        assert_eq!(
+            "........................ W 9".parse::<Game>().err(),
+            Some(LoadError::Parse("Invalid position format"))
+        );
+        assert_eq!(
+            "........................ W 9 x".parse::<Game>().err(),
+            Some(LoadError::Parse("Invalid unplaced count"))
+        );
+        assert_eq!(
+            "WWWWWWWWWW.............. W".parse::<Game>().err(),
+            Some(LoadError::Position("Too many pieces"))
+        );
+    }
+
+    #[test]
+    fn test_no_progress_draw() {
+        let mut game = setup(&[0, 9, 20], &[4, 13, 16]);
+        for ply in 0..Game::NO_PROGRESS_LIMIT {
+            assert_eq!(game.result(), GameResult::Ongoing, "ply {ply}");
+            // take the first move that closes no mill and reaches a new position
+            let mut found = false;
+            'search: for from in 0..24 {
+                for to in 0..24 {
+                    let action = Action {
+                        player: game.to_move(),
+                        action: ActionKind::Move(from, to),
+                    };
+                    if game.action(action).is_err() {
+                        continue;
+                    }
+                    if game.last_mill().is_none() && game.repetition_count() == 1 {
+                        found = true;
+                        break 'search;
+                    }
+                    game.undo().unwrap();
+                }
+            }
+            assert!(found);
+        }
+        assert_eq!(game.quiet_moves, Game::NO_PROGRESS_LIMIT);
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::NoProgress));
+        game.undo().unwrap();
+        assert_eq!(game.result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn test_legal_moves_match_is_legal() {
+        let positions = [
+            Game::new(),
+            setup(&[0, 1, 3, 20], &[4, 5, 11, 22]),
+            setup(&[0, 9, 20], &[4, 13, 16, 17, 18]),
+        ];
+        for mut game in positions {
+            for _ in 0..2 {
+                let mut expected = Vec::new();
+                for player in [Player::White, Player::Black] {
+                    for p in 0..24 {
+                        expected.push(Action {
+                            player,
+                            action: ActionKind::Place(p),
+                        });
+                        expected.push(Action {
+                            player,
+                            action: ActionKind::Remove(p),
+                        });
+                        for q in 0..24 {
+                            expected.push(Action {
+                                player,
+                                action: ActionKind::Move(p, q),
+                            });
+                        }
+                    }
+                }
+                expected.retain(|&a| game.is_legal(a));
+                let mut moves = game.legal_moves();
+                let key = |a: &Action| a.to_string();
+                expected.sort_by_key(key);
+                moves.sort_by_key(key);
+                assert_eq!(moves, expected);
+                // also look at the position after the first legal action
+                let first = game.legal_moves()[0];
+                game.action(first).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_opening() {
+        let game = Game::new();
+        let expected = [1, 24, 552, 12_144, 255_024];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(game.perft(depth as u32), nodes, "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn test_perft_counts_removals() {
+        let mut game = setup(&[0, 1, 3, 20], &[4, 5, 11, 22]);
+        // 3 -> 2 closes [0, 1, 2], after which any of the 4 black pieces may go
+        play(&mut game, &["W M 3 2"]);
+        assert_eq!(game.must_remove(), Some(Player::White));
+        assert_eq!(game.perft(1), 4);
+    }
+
+    #[test]
+    fn test_variant_tables() {
+        for variant in [Variant::Three, Variant::Six, Variant::Nine, Variant::Twelve] {
+            let count = variant.point_count();
+            for &mill in variant.mills() {
+                assert!(mill.iter().all(|&p| p < count));
+                assert!(variant.are_adjacent(mill[0], mill[1]));
+                assert!(variant.are_adjacent(mill[1], mill[2]));
+            }
+            for a in 0..24 {
+                assert_eq!(variant.neighbors(a).is_empty(), a >= count);
+                for b in 0..24 {
+                    assert_eq!(variant.are_adjacent(a, b), variant.are_adjacent(b, a));
+                }
+            }
+        }
+        for p in 0..24 {
+            assert_eq!(Variant::Nine.neighbors(p), Game::neighbors(p));
+        }
+        assert_eq!(Variant::Three.neighbors(8), (0..8).collect::<Vec<_>>());
+        assert_eq!(Variant::Twelve.neighbors(8), vec![0, 9, 15, 16]);
+        assert_eq!(Variant::Six.neighbors(9), vec![1, 8, 10]);
+
+        let symmetries = |variant| Game::with_variant(variant).symmetries().count();
+        assert_eq!(symmetries(Variant::Three), 8);
+        assert_eq!(symmetries(Variant::Six), 8);
+        assert_eq!(symmetries(Variant::Nine), 16);
+        assert_eq!(symmetries(Variant::Twelve), 16);
+    }
+
+    #[test]
+    fn test_topology() {
+        let topology = Game::new().topology();
+        assert_eq!(topology.point_count(), 24);
+        assert_eq!(topology.mills().len(), 16);
+        let degrees: Vec<usize> = (0..24).map(|p| topology.neighbors(p).len()).collect();
+        assert_eq!(degrees.iter().filter(|&&d| d == 2).count(), 12);
+        assert_eq!(degrees.iter().filter(|&&d| d == 3).count(), 8);
+        assert_eq!(degrees.iter().filter(|&&d| d == 4).count(), 4);
+        assert_eq!(topology.neighbors(9), vec![1, 8, 10, 17]);
+        for p in 0..24 {
+            assert_eq!(topology.point_mills(p), Game::POINT_MILLS[p]);
+        }
+        assert!(topology.point_mills(24).is_empty());
+
+        let six = Variant::Six.topology();
+        assert_eq!((six.point_count(), six.mills().len()), (16, 8));
+        assert_eq!(Variant::Twelve.topology().point_mills(0), vec![0, 3, 16]);
+        assert_eq!(Variant::Three.topology().point_mills(8).len(), 4);
+    }
+
+    #[test]
+    fn test_twelve_mens_diagonals() {
+        let mut game = Game::with_variant(Variant::Twelve);
+        assert_eq!(game.remaining_unplaced(Player::White), 12);
+        play(&mut game, &["W P 0", "B P 1", "W P 8", "B P 2"]);
+        play(&mut game, &["W P 16"]);
+        assert_eq!(game.last_mill(), Some([0, 8, 16]));
+        assert_eq!(game.must_remove(), Some(Player::White));
+        play(&mut game, &["W R 1"]);
+        assert_eq!(game.removed_count(Player::Black), 1);
+
+        let mut nine = setup(&[0, 4, 12, 21], &[5, 13, 22, 23]);
+        let mut twelve = nine.clone();
+        twelve.config.variant = Variant::Twelve;
+        assert!(!nine.is_legal("W M 0 8".parse().unwrap()));
+        assert!(twelve.is_legal("W M 0 8".parse().unwrap()));
+        // closes the diagonal [4, 12, 20]
+        assert!(nine.action("W M 21 20".parse().unwrap()).is_ok());
+        assert_eq!(nine.must_remove(), None);
+        assert!(twelve.action("W M 21 20".parse().unwrap()).is_ok());
+        assert_eq!(twelve.last_mill(), Some([4, 12, 20]));
+        assert_eq!(twelve.must_remove(), Some(Player::White));
+    }
+
+    #[test]
+    fn test_six_mens_morris() {
+        let mut game = Game::with_variant(Variant::Six);
+        assert_eq!(game.remaining_unplaced(Player::Black), 6);
+        assert!(game.action("W P 16".parse().unwrap()).is_err());
+        play(&mut game, &["W P 9", "B P 1"]);
+        assert_eq!(game.legal_moves().len(), 14);
+        // no mill along the spokes
+        play(
+            &mut game,
+            &["W P 3", "B P 11", "W P 5", "B P 13", "W P 15", "B P 7"],
+        );
+        play(&mut game, &["W P 0", "B P 8", "W P 12", "B P 4"]);
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert!(!game.is_legal("W M 9 17".parse().unwrap()));
+        assert!(game.is_legal("W M 9 10".parse().unwrap()));
+
+        // three pieces left do not fly
+        let mut six = Game::with_variant(Variant::Six);
+        for p in [0, 10, 15] {
+            six.set_point(p, Some(Color::White));
+        }
+        for p in [1, 2, 4] {
+            six.set_point(p, Some(Color::Black));
+        }
+        six.unplaced = [0, 0];
+        assert_eq!(six.phase(Player::White), Phase::Movement);
+        assert!(!six.is_legal("W M 0 6".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_three_mens_morris() {
+        let mut game = Game::with_variant(Variant::Three);
+        assert!(game.action("W P 9".parse().unwrap()).is_err());
+        play(&mut game, &["W P 0", "B P 1", "W P 8", "B P 2", "W P 4"]);
+        assert_eq!(game.last_mill(), Some([0, 8, 4]));
+        play(&mut game, &["W R 1"]);
+        assert_eq!(game.winner(), Some(Player::White));
+        assert_eq!(game.to_string(), "W-.-B\n|\\|/|\n.-W-.\n|/|\\|\n.-.-W");
+    }
+
+    #[test]
+    fn test_display_six_mens() {
+        let mut game = Game::with_variant(Variant::Six);
+        play(&mut game, &["W P 15", "B P 3"]);
+        let expected = "\
+.-----.-----.
+| .---.---. |
+| |       | |
+.-W       .-B
+| |       | |
+| .---.---. |
+.-----.-----.";
+        assert_eq!(game.to_string(), expected);
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = GameConfig::default();
+        assert_eq!(config.variant, Variant::Nine);
+        assert!(config.flying_enabled);
+        assert_eq!(config.loss_threshold, 7);
+        assert_eq!(Game::new().config(), config);
+    }
+
+    #[test]
+    fn test_flying_disabled() {
+        // White's three pieces are all surrounded
+        let mut game = setup(&[0, 2, 4], &[1, 3, 5, 7, 6]);
+        assert_eq!(game.phase(Player::White), Phase::Flying);
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert!(game.is_legal("W M 0 10".parse().unwrap()));
+
+        game.config.flying_enabled = false;
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert!(!game.is_legal("W M 0 10".parse().unwrap()));
+        assert_eq!(game.result(), GameResult::Win(Player::Black));
+    }
+
+    #[test]
+    fn test_loss_threshold() {
+        let mut game = Game::with_config(GameConfig {
+            loss_threshold: 1,
+            ..GameConfig::default()
+        });
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(game.winner(), None);
+        play(&mut game, &["W R 4"]);
+        assert_eq!(game.winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn test_redo() {
+        let mut game = Game::new();
+        assert!(game.redo().is_err());
+        play(&mut game, &["W P 0", "B P 3"]);
+        let placed = game.clone();
+
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.points()[0], None);
+        game.redo().unwrap();
+        assert_eq!(game.points()[0], Some(Piece::White));
+        game.redo().unwrap();
+        assert_eq!(game.points(), placed.points());
+        assert_eq!(game.to_move(), placed.to_move());
+        assert!(game.redo().is_err());
+
+        // redone actions can be undone again
+        game.undo().unwrap();
+        assert_eq!(game.points()[3], None);
+    }
+
+    #[test]
+    fn test_action_clears_redo() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0"]);
+        game.undo().unwrap();
+        play(&mut game, &["W P 1"]);
+        assert!(game.redo().is_err());
+        assert_eq!(game.points()[0], None);
+        assert_eq!(game.points()[1], Some(Piece::White));
+    }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let actions = [
+            "W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4", "B P 5", "W P 15",
+        ];
+        let mut game = Game::new();
+        assert_eq!(game.transcript(), "");
+        play(&mut game, &actions);
+        assert_eq!(game.transcript(), actions.join("\n") + "\n");
+
+        let replayed = Game::replay(&game.transcript()).unwrap();
+        assert_eq!(replayed.points(), game.points());
+        assert_eq!(replayed.to_move(), game.to_move());
+        assert_eq!(replayed.transcript(), game.transcript());
+
+        game.undo().unwrap();
+        assert_eq!(game.transcript(), actions[..7].join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_transcript_moves() {
+        let mut game = setup(&[0, 2, 4, 9], &[11, 13, 15, 17]);
+        play(&mut game, &["W M 9 1", "W R 11", "B M 13 12"]);
+        assert_eq!(game.transcript(), "W M 9 1\nW R 11\nB M 13 12\n");
+    }
+
+    #[test]
+    fn test_replay_errors() {
+        assert_eq!(
+            Game::replay("W P 0\nB X 1").err(),
+            Some(LoadError::Parse("Invalid action type"))
+        );
+        assert_eq!(
+            Game::replay("W P 0\nB P 0").err(),
+            Some(LoadError::Action(ActionError::Occupied))
+        );
+        assert!(Game::replay("\nW P 0\n\n").is_ok());
+    }
+
+    #[test]
+    fn test_move_log() {
+        let mut game = Game::new();
+        assert!(game.move_log().is_empty());
+        let actions = ["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"];
+        play(&mut game, &actions);
+        let expected: Vec<Action> = actions.iter().map(|a| a.parse().unwrap()).collect();
+        assert_eq!(game.move_log(), &expected[..]);
+        assert_eq!(
+            game.move_log().last().unwrap().action,
+            ActionKind::Remove(4)
+        );
+
+        // rejected actions are not logged
+        assert!(game.action("W P 0".parse().unwrap()).is_err());
+        assert_eq!(game.move_log().len(), 6);
+
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert_eq!(game.move_log(), &expected[..4]);
+        game.redo().unwrap();
+        assert_eq!(game.move_log(), &expected[..5]);
+    }
+
+    #[test]
+    fn test_apply_all() {
+        let actions: Vec<Action> = ["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        let mut game = Game::new();
+        assert_eq!(game.apply_all(&actions), Ok(6));
+        assert_eq!(game.move_log(), &actions[..]);
+        assert_eq!(game.apply_all(&[]), Ok(0));
+    }
+
+    #[test]
+    fn test_replay_cursor() {
+        let mut game = Game::new();
+        let actions = [
+            "W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4", "B P 4",
+        ];
+        play(&mut game, &actions);
+        let mut replay = Replay::new(game.move_log()).unwrap();
+        assert_eq!(replay.len(), 7);
+        assert_eq!(replay.current().points(), Game::new().points());
+
+        replay.seek(3);
+        let mut expected = Game::new();
+        play(&mut expected, &actions[..3]);
+        assert_eq!(replay.ply(), 3);
+        assert_eq!(replay.current().position(), expected.position());
+
+        // just after the mill, before the removal
+        replay.step_forward();
+        replay.step_forward();
+        assert_eq!(replay.current().must_remove(), Some(Player::White));
+        replay.step_back();
+        assert_eq!(replay.ply(), 4);
+        assert_eq!(replay.current().points()[7], None);
+
+        replay.seek(100);
+        assert_eq!(replay.ply(), 7);
+        assert_eq!(replay.current().position(), game.position());
+        replay.step_forward();
+        assert_eq!(replay.ply(), 7);
+
+        replay.seek(0);
+        replay.step_back();
+        assert_eq!(replay.ply(), 0);
+        assert_eq!(replay.current().position(), Game::new().position());
+
+        let illegal: Vec<Action> = ["W P 0", "B P 0"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        assert_eq!(
+            Replay::new(&illegal).err(),
+            Some((1, ActionError::Occupied))
+        );
+        assert!(Replay::new(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_all_stops_at_failure() {
+        let actions: Vec<Action> = ["W P 0", "B P 3", "W P 3", "B P 4"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        let mut game = Game::new();
+        assert_eq!(game.apply_all(&actions), Err((2, ActionError::Occupied)));
+        assert_eq!(game.move_log(), &actions[..2]);
+        assert_eq!(game.points()[3], Some(Piece::Black));
+        assert_eq!(game.points()[4], None);
+        assert_eq!(game.to_move(), Player::White);
+    }
+
+    #[test]
+    fn test_stalemate_is_draw() {
+        // White's corners are all blocked by Black
+        let mut game = setup(&[0, 2, 4, 6], &[1, 3, 5, 7]);
+        assert!(!game.config().stalemate_is_draw);
+        assert_eq!(game.result(), GameResult::Win(Player::Black));
+
+        game.config.stalemate_is_draw = true;
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Stalemate));
+        assert!(game.is_draw());
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn test_stalemate_with_three_pieces() {
+        let mut game = setup(&[0, 2, 4], &[1, 3, 5, 7, 6]);
+        game.config.stalemate_is_draw = true;
+        // flying always finds an empty point
+        assert_eq!(game.result(), GameResult::Ongoing);
+
+        game.config.flying_enabled = false;
+        assert_eq!(game.result(), GameResult::Draw(DrawReason::Stalemate));
+    }
+
+    #[test]
+    fn test_pieces() {
+        let mut game = Game::new();
+        assert_eq!(game.pieces().count(), 0);
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        assert_eq!(
+            game.pieces().collect::<Vec<_>>(),
+            [
+                (0, Piece::White),
+                (3, Piece::Black),
+                (6, Piece::White),
+                (7, Piece::White)
+            ]
+        );
+        assert_eq!(game.pieces_of(Color::White).collect::<Vec<_>>(), [0, 6, 7]);
+        assert_eq!(game.pieces_of(Color::Black).collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn test_coordinates_round_trip() {
+        for point in 0..24 {
+            assert_eq!(coord_to_point(&point_to_coord(point)), Some(point));
+        }
+        assert_eq!(point_to_coord(0), "a7");
+        assert_eq!(point_to_coord(4), "g1");
+        assert_eq!(point_to_coord(11), "f4");
+        assert_eq!(point_to_coord(22), "c3");
+        assert_eq!(coord_to_point("D5"), Some(17));
+        assert_eq!(coord_to_point("d4"), None);
+        assert_eq!(coord_to_point("a2"), None);
+        assert_eq!(coord_to_point(""), None);
+    }
+
+    #[test]
+    fn test_win_reason() {
+        let mut game = Game::new();
+        assert_eq!(game.win_reason(), None);
+        game.removed = [0, 7];
+        assert_eq!(game.win_reason(), Some(WinReason::SevenRemoved));
+
+        let game = setup(&[0, 2, 4, 6], &[1, 3, 5, 7]);
+        assert_eq!(game.winner(), Some(Player::Black));
+        assert_eq!(game.win_reason(), Some(WinReason::NoLegalMoves));
+
+        let mut game = Game::new();
+        for action in include_str!("../tests/example_games/game1.txt").lines() {
+            game.action(action.parse().unwrap()).unwrap();
+        }
+        assert_eq!(game.win_reason(), Some(WinReason::SevenRemoved));
+    }
+
+    #[test]
+    fn test_two_pieces_lose() {
+        // the loss threshold alone would let Black play on with two pieces
+        let config = GameConfig {
+            loss_threshold: 9,
+            ..GameConfig::default()
+        };
+        let mut game = GameBuilder::new()
+            .config(config)
+            .place(0, Color::White)
+            .place(6, Color::White)
+            .place(15, Color::White)
+            .place(20, Color::White)
+            .place(3, Color::Black)
+            .place(10, Color::Black)
+            .place(12, Color::Black)
+            .removed(Color::White, 5)
+            .removed(Color::Black, 6)
+            .build()
+            .unwrap();
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.winner(), None);
+        play(&mut game, &["W R 3"]);
+        assert_eq!(game.winner(), Some(Player::White));
+        assert_eq!(game.win_reason(), Some(WinReason::TooFewPieces));
+        assert_eq!(game.summary(), "White wins, Black has 2 pieces left");
+
+        // during placement two pieces on the board are no loss yet
+        let mut game = Game::with_config(config);
+        play(&mut game, &["W P 0", "B P 3", "W P 1"]);
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3"]);
+        game.resign(Player::White);
+        assert_eq!(game.winner(), Some(Player::Black));
+        assert_eq!(game.win_reason(), Some(WinReason::Resignation));
+        assert_eq!(game.summary(), "Black wins, White resigned");
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(
+            game.action("W P 1".parse().unwrap()),
+            Err(ActionError::GameResigned)
+        );
+
+        // undo takes back the resignation first, then the actions
+        assert_eq!(game.undo(), Ok(()));
+        assert_eq!(game.result(), GameResult::Ongoing);
+        assert_eq!(game.board_string(), "W..B....................");
+        play(&mut game, &["W P 1"]);
+        game.resign(Player::Black);
+        assert_eq!(game.undo_n(5), Ok(4));
+        assert_eq!(game.points(), Game::new().points());
+
+        // resigning a finished game changes nothing
+        let mut game = setup(&[0, 2, 4, 6], &[1, 3, 5, 7]);
+        game.resign(Player::Black);
+        assert_eq!(game.winner(), Some(Player::Black));
+        assert_eq!(game.win_reason(), Some(WinReason::NoLegalMoves));
+    }
+
+    #[test]
+    fn test_captures_by() {
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        play(&mut game, &["W M 15 7", "W R 17", "B M 3 2", "W M 7 15"]);
+        play(&mut game, &["B M 2 3", "W M 15 7", "W R 4"]);
+        assert_eq!(game.captures_by(Player::White), [17, 4]);
+        assert!(game.captures_by(Player::Black).is_empty());
+
+        game.undo().unwrap();
+        assert_eq!(game.captures_by(Player::White), [17]);
+        game.redo().unwrap();
+        assert_eq!(game.captures_by(Player::White), [17, 4]);
+        game.reset();
+        assert!(game.captures_by(Player::White).is_empty());
+    }
+
+    #[test]
+    fn test_count_mills() {
+        // two mills sharing the corner 0
+        let game = setup(&[0, 1, 2, 6, 7, 9], &[3, 11, 19, 12]);
+        assert_eq!(game.count_mills(Color::White), 2);
+        assert_eq!(game.count_mills(Color::Black), 1);
+        assert_eq!(Game::new().count_mills(Color::White), 0);
+    }
+
+    #[test]
+    fn test_count_pieces() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        assert_eq!(game.count_pieces(Color::White), 3);
+        assert_eq!(game.count_pieces(Color::Black), 1);
+        assert_eq!(game.remaining_unplaced(Color::Black), 7);
+    }
+
+    #[test]
+    fn test_is_blocked() {
+        // the corner 0 is surrounded by black pieces
+        let game = setup(&[0, 9, 10, 11], &[1, 7, 2, 3]);
+        assert!(game.is_blocked(0));
+        assert!(game.is_blocked(1));
+        assert!(!game.is_blocked(3));
+        assert!(!game.is_blocked(9));
+        // empty points are never blocked
+        assert!(!game.is_blocked(5));
+    }
+
+    #[test]
+    fn test_frontier() {
+        let mut game = Game::new();
+        assert!(game.frontier(Player::White).is_empty());
+        play(&mut game, &["W P 0", "B P 9", "W P 1"]);
+        // 0 and 1 touch 2, 7 and 9, but Black holds 9
+        assert_eq!(game.frontier(Player::White), vec![2, 7]);
+        assert_eq!(game.frontier(Player::Black), vec![8, 10, 17]);
+
+        let game = setup(&[0, 2, 4, 6], &[1, 3, 5, 7]);
+        assert_eq!(game.frontier(Player::White), Vec::<Point>::new());
+        assert_eq!(game.frontier(Player::Black), vec![9, 11, 13, 15]);
+    }
+
+    #[test]
+    fn test_mobility() {
+        let mut game = setup(&[0, 2, 4, 6], &[1, 3, 5, 7]);
+        assert_eq!(game.mobility(Player::White), 0);
+        // each black piece can only go inwards
+        assert_eq!(game.mobility(Player::Black), 4);
+        game.to_move = Player::Black;
+        assert_eq!(game.legal_moves().len(), 4);
+
+        assert_eq!(Game::new().mobility(Player::White), 24);
+        let flying = setup(&[0, 2, 4], &[1, 3, 5, 7, 6]);
+        assert_eq!(flying.mobility(Player::White), 3 * 16);
+    }
+
+    #[test]
+    fn test_open_mills() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 10", "W P 1"]);
+        assert_eq!(game.open_mills(Color::White), [[0, 1, 2]]);
+        assert!(game.open_mills(Color::Black).is_empty());
+        assert_eq!(game.closable_mills(Color::White), [[0, 1, 2]]);
+
+        play(&mut game, &["B P 2"]);
+        assert!(game.open_mills(Color::White).is_empty());
+    }
+
+    #[test]
+    fn test_closable_mills() {
+        // 0-1-2 is open, but only a piece from inside the mill could reach 2
+        let game = setup(&[0, 1, 12, 20], &[3, 5, 8, 14, 18]);
+        assert_eq!(game.open_mills(Color::White), [[0, 1, 2]]);
+        assert!(game.closable_mills(Color::White).is_empty());
+
+        let game = setup(&[0, 1, 3, 20], &[5, 8, 14, 18]);
+        assert_eq!(game.closable_mills(Color::White), [[0, 1, 2]]);
+
+        // with three pieces left, 20 can fly to 2
+        let game = setup(&[0, 1, 20], &[3, 5, 8, 14, 18]);
+        assert_eq!(game.closable_mills(Color::White), [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_swinging_mills() {
+        // 2 shuttles between the closed mill 0-1-2 and the open mill 3-11-19
+        let mut game = setup(&[0, 1, 2, 11, 19], &[4, 6, 14, 22]);
+        assert_eq!(
+            game.swinging_mills(Color::White),
+            [(2, [0, 1, 2], [3, 11, 19])]
+        );
+        assert!(game.swinging_mills(Color::Black).is_empty());
+
+        play(&mut game, &["W M 2 3", "W R 4"]);
+        assert_eq!(
+            game.swinging_mills(Color::White),
+            [(3, [3, 11, 19], [0, 1, 2])]
+        );
+    }
+
+    #[test]
+    fn test_board_transforms() {
+        let original = setup(&[0, 1, 9, 20], &[3, 12, 23]);
+        let mut game = original.clone();
+        for _ in 0..4 {
+            game.set_board(game.rotate90());
+        }
+        assert_eq!(game.points(), original.points());
+
+        let rotated = original.rotate90();
+        assert_eq!(rotated[2], Some(Piece::White));
+        assert_eq!(rotated[5], Some(Piece::Black));
+        assert_eq!(rotated[22], Some(Piece::White));
+
+        let mirrored = original.mirror_horizontal();
+        assert_eq!(mirrored[2], Some(Piece::White));
+        assert_eq!(mirrored[7], Some(Piece::Black));
+        game.set_board(mirrored);
+        assert_eq!(game.mirror_horizontal(), *original.points());
+
+        let swapped = original.swap_rings();
+        assert_eq!(swapped[16], Some(Piece::White));
+        assert_eq!(swapped[4], Some(Piece::White));
+        assert_eq!(swapped[9], Some(Piece::White));
+        game.set_board(swapped);
+        assert_eq!(game.swap_rings(), *original.points());
+    }
+
+    #[test]
+    fn test_is_material_drawish() {
+        // no two pieces of the same color share a line
+        let game = setup(&[0, 4, 13], &[9, 11, 22]);
+        assert!(game.is_material_drawish());
+
+        let game = setup(&[0, 1, 13], &[9, 11, 22]);
+        assert!(!game.is_material_drawish());
+        let game = setup(&[0, 4, 13, 18], &[9, 11, 22]);
+        assert!(!game.is_material_drawish());
+        assert!(!Game::new().is_material_drawish());
+    }
+
+    #[test]
+    fn test_board_view() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        let view = game.board_view();
+        assert_eq!(
+            view[7],
+            Square {
+                point: 7,
+                piece: Some(Piece::White),
+                in_mill: true
+            }
+        );
+        assert!(view[0].in_mill && view[6].in_mill);
+        assert_eq!(view[3].piece, Some(Piece::Black));
+        assert!(!view[3].in_mill);
+        assert_eq!(view[12].piece, None);
+        assert!(view.iter().enumerate().all(|(p, square)| square.point == p));
+    }
+
+    #[test]
+    fn test_position_round_trip() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        let position = game.position();
+        assert_eq!(position.must_remove, Some(Player::White));
+
+        let mut restored = Game::from_position(position).unwrap();
+        assert_eq!(restored.position(), position);
+        assert_eq!(restored.zobrist_hash(), game.zobrist_hash());
+        assert!(restored.undo().is_err());
+        play(&mut restored, &["W R 4"]);
+        play(&mut game, &["W R 4"]);
+        assert_eq!(restored.position(), game.position());
+    }
+
+    #[test]
+    fn test_from_position_rejects_inconsistent() {
+        let mut position = Game::new().position();
+        position.board[5] = Some(Piece::White);
+        position.to_move = Player::Black;
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Piece counts do not add up")
+        );
+        position.unplaced[0] -= 1;
+        assert!(Game::from_position(position).is_ok());
+
+        position.must_remove = Some(Player::Black);
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Removal pending without a mill")
+        );
+
+        let mut position = Game::with_variant(Variant::Six).position();
+        position.board[20] = Some(Piece::White);
+        position.unplaced[0] -= 1;
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Piece outside the board")
+        );
+    }
+
+    #[test]
+    fn test_from_setup() {
+        // White has just closed 6-7-0 and takes a piece next
+        let mut board = [None; 24];
+        for p in [0, 6, 7, 20] {
+            board[p] = Some(Piece::White);
+        }
+        for p in [3, 4, 10, 12, 17] {
+            board[p] = Some(Piece::Black);
+        }
+        let mut game =
+            Game::from_setup(board, Player::White, [0, 0], [5, 4], Some(Player::White)).unwrap();
+        assert_eq!(game.undo(), Err(ActionError::NothingToUndo));
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert!(
+            game.legal_moves()
+                .iter()
+                .all(|a| matches!(a.action, ActionKind::Remove(_)))
+        );
+        play(&mut game, &["W R 17"]);
+        assert_eq!(game.to_move(), Player::Black);
+        assert!(game.undo().is_ok());
+        assert_eq!(game.undo(), Err(ActionError::NothingToUndo));
+
+        // ten White pieces on the board
+        let board = std::array::from_fn(|p| (p < 10).then_some(Piece::White));
+        assert_eq!(
+            Game::from_setup(board, Player::Black, [0, 0], [0, 9], None).err(),
+            Some("Piece counts do not add up")
+        );
+        // no mill to earn the removal
+        assert_eq!(
+            Game::from_setup(
+                [None; 24],
+                Player::White,
+                [9, 9],
+                [0, 0],
+                Some(Player::White)
+            )
+            .err(),
+            Some("Removal pending without a mill")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut game = Game::new();
+        assert_eq!(game.validate(), Ok(()));
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(game.validate(), Ok(()));
+
+        let mut broken = game.clone();
+        broken.set_point(12, Some(Piece::Black));
+        assert_eq!(broken.validate(), Err("Piece counts do not add up"));
+
+        let mut broken = game.clone();
+        broken.to_move = Player::Black;
+        broken.must_remove = Some(Player::Black);
+        assert_eq!(broken.validate(), Err("Removal pending without a mill"));
+        broken.must_remove = Some(Player::White);
+        assert_eq!(
+            broken.validate(),
+            Err("Removal pending for the wrong player")
+        );
+
+        // the mill behind a pending removal must be a real mill on the board
+        let mut position = game.position();
+        position.must_remove = Some(Player::White);
+        position.last_mill = Some([0, 1, 99]);
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Mill outside the board")
+        );
+        position.last_mill = Some([0, 1, 0]);
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Not a mill of the variant")
+        );
+        position.must_remove = None;
+        assert_eq!(
+            Game::from_position(position).err(),
+            Some("Not a mill of the variant")
+        );
+
+        // Black cannot have placed before White
+        let mut broken = Game::new();
+        broken.set_point(7, Some(Piece::Black));
+        broken.unplaced[1] -= 1;
+        assert_eq!(broken.validate(), Err("Players did not place alternately"));
+
+        // parsing checks the position like `from_position`
+        assert_eq!(
+            "........................ W 9 8".parse::<Game>().err(),
+            Some(LoadError::Position("Players did not place alternately"))
+        );
+        let valid: Game = "WW.WBB.....B........W.B.".parse().unwrap();
+        assert_eq!(valid.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_action_events() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        assert_eq!(
+            game.action_events("W P 7".parse().unwrap()),
+            Ok(vec![
+                GameEvent::Placed {
+                    player: Player::White,
+                    point: 7
+                },
+                GameEvent::MillFormed {
+                    player: Player::White,
+                    mill: [6, 7, 0]
+                },
+            ])
+        );
+        assert_eq!(
+            game.action_events("W R 4".parse().unwrap()),
+            Ok(vec![
+                GameEvent::Removed {
+                    player: Player::White,
+                    point: 4
+                },
+                GameEvent::TurnPassed {
+                    player: Player::Black
+                },
+            ])
+        );
+        assert_eq!(
+            game.action_events("W P 1".parse().unwrap()),
+            Err(ActionError::NotYourTurn)
+        );
+        assert_eq!(game.move_log().len(), 6);
+
+        // every black piece is protected, so the mill passes the turn
+        let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+        game.config.mill_removal_policy = MillRemovalPolicy::NeverFromMill;
+        assert_eq!(
+            game.action_events("W M 15 7".parse().unwrap()),
+            Ok(vec![
+                GameEvent::Moved {
+                    player: Player::White,
+                    from: 15,
+                    to: 7
+                },
+                GameEvent::MillWithoutRemoval {
+                    player: Player::White,
+                    mill: [6, 7, 0]
+                },
+                GameEvent::TurnPassed {
+                    player: Player::Black
+                },
+            ])
+        );
+    }
+
+    // Calls `check` on every position of a few games per variant, picking actions with a
+    // fixed pseudo-random sequence.
+    fn random_positions(mut check: impl FnMut(&Game)) {
+        let mut state: u64 = 12345;
+        for variant in [Variant::Three, Variant::Six, Variant::Nine, Variant::Twelve] {
+            for _ in 0..20 {
+                let mut game = Game::with_variant(variant);
+                for _ in 0..200 {
+                    check(&game);
+                    let moves = game.legal_moves();
+                    if game.result() != GameResult::Ongoing || moves.is_empty() {
+                        break;
+                    }
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    let action = moves[(state >> 33) as usize % moves.len()];
+                    game.action(action).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_forced_move() {
+        // Black's corners are boxed in, only 22 can go to 23
+        let mut game = setup(&[1, 3, 5, 7, 21], &[0, 2, 4, 22]);
+        game.to_move = Player::Black;
+        assert_eq!(game.forced_move(), "B M 22 23".parse().ok());
+
+        // 5-13-21 is a mill, so 3 is the only piece to take
+        let mut game = setup(&[0, 6, 15, 20], &[3, 5, 13, 21]);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.forced_move(), "W R 3".parse().ok());
+
+        assert_eq!(Game::new().forced_move(), None);
+        let mut game = setup(&[1, 3, 5, 7], &[0, 2, 4, 6]);
+        game.to_move = Player::Black;
+        assert_eq!(game.forced_move(), None);
+    }
+
+    #[test]
+    fn test_pseudo_legal_moves() {
+        // Black's corners are boxed in except for 22, which can go to 23
+        let game = setup(&[1, 3, 5, 7, 21], &[0, 2, 4, 22]);
+        assert_eq!(game.pseudo_legal_moves(Player::White), game.legal_moves());
+        assert_eq!(game.pseudo_legal_moves(Player::White).len(), 8);
+        assert_eq!(
+            game.pseudo_legal_moves(Player::Black),
+            ["B M 22 23".parse().unwrap()]
+        );
+
+        // also during placement and while a removal is pending
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(game.pseudo_legal_moves(Player::White).len(), 19);
+        assert!(
+            game.pseudo_legal_moves(Player::Black)
+                .iter()
+                .all(|a| a.player == Player::Black && matches!(a.action, ActionKind::Place(_)))
+        );
+    }
+
+    #[test]
+    fn test_placement_targets() {
+        let mut game = Game::new();
+        assert_eq!(game.placement_targets(), (0..24).collect::<Vec<_>>());
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4"]);
+        let targets = game.placement_targets();
+        assert_eq!(targets.len(), 20);
+        assert!(![0, 3, 4, 6].iter().any(|p| targets.contains(p)));
+
+        // closing 6-7-0 leaves a removal pending
+        play(&mut game, &["W P 7"]);
+        assert!(game.placement_targets().is_empty());
+
+        let game = Game::with_variant(Variant::Three);
+        assert_eq!(game.placement_targets().len(), 9);
+
+        let game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        assert!(game.placement_targets().is_empty());
+    }
+
+    #[test]
+    fn test_moves_from() {
+        let game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        assert_eq!(game.moves_from(15), [7, 8, 14, 23]);
+        assert_eq!(game.moves_from(20), [19, 21]);
+        // Black's piece, an empty point and a point off the board
+        assert!(game.moves_from(3).is_empty());
+        assert!(game.moves_from(1).is_empty());
+        assert!(game.moves_from(99).is_empty());
+
+        // Black's corner 4 is boxed in by 3 and 5
+        let mut game = setup(&[0, 5, 6, 15], &[3, 4, 10, 12, 17]);
+        game.to_move = Player::Black;
+        assert!(game.moves_from(4).is_empty());
+        assert_eq!(game.moves_from(3), [2, 11]);
+
+        // flying: every empty point
+        let game = setup(&[0, 6, 15], &[3, 4, 10, 12, 17]);
+        assert_eq!(game.moves_from(0).len(), 16);
+
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3"]);
+        assert!(game.moves_from(0).is_empty());
+    }
+
+    #[test]
+    fn test_last_removed() {
+        let mut game = Game::new();
+        assert_eq!(game.last_removed(), None);
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(game.last_removed(), None);
+        play(&mut game, &["W R 4"]);
+        assert_eq!(game.last_removed(), Some((4, Piece::Black)));
+
+        play(&mut game, &["B P 4"]);
+        assert_eq!(game.last_removed(), None);
+        game.undo().unwrap();
+        assert_eq!(game.last_removed(), Some((4, Piece::Black)));
+        game.undo().unwrap();
+        assert_eq!(game.last_removed(), None);
+    }
+
+    #[test]
+    fn test_opponent_threats() {
+        // White can close 6-7-0 by moving 15 to 7, but nothing can reach 19 to close 18-19-20
+        let game = setup(&[0, 6, 15, 18, 20], &[3, 4, 10, 12, 17]);
+        assert_eq!(game.open_mills(Color::White), [[6, 7, 0], [18, 19, 20]]);
+        assert_eq!(game.opponent_threats(Player::Black), [[6, 7, 0]]);
+        // Black can close 10-11-12 from 3, but nothing reaches 2 to close 2-3-4
+        assert_eq!(game.opponent_threats(Player::White), [[10, 11, 12]]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut game = Game::new();
+        assert_eq!(
+            game.summary(),
+            "White to move (placement, 9 left); Black to move next; no winner"
+        );
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(
+            game.summary(),
+            "White to remove a piece (placement, 6 left); Black to move next; no winner"
+        );
+
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10]);
+        assert_eq!(
+            game.summary(),
+            "White to move (movement); Black to move next; no winner"
+        );
+        play(&mut game, &["W M 15 7", "W R 3"]);
+        assert_eq!(game.summary(), "White wins by capturing 7");
+
+        let mut game = setup(&[1, 3, 5, 7], &[0, 2, 4, 6]);
+        game.to_move = Player::Black;
+        assert_eq!(game.summary(), "White wins, Black cannot move");
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3"]);
+        let before = game.position();
+
+        let mut fork = game.fork();
+        play(&mut fork, &["W P 6", "B P 4", "W P 7", "W R 4"]);
+        fork.undo_n(5).unwrap();
+        assert_eq!(fork.move_log().len(), 1);
+        fork.redo().unwrap();
+        play(&mut fork, &["W P 9"]);
+
+        assert_eq!(game.position(), before);
+        assert_eq!(game.move_log().len(), 2);
+        assert!(game.redo().is_err());
+        game.undo().unwrap();
+        assert_eq!(game.points()[3], None);
+        assert_eq!(fork.points()[3], Some(Piece::Black));
+    }
+
+    #[test]
+    fn test_successors() {
+        let game = Game::new();
+        let successors = game.successors();
+        assert_eq!(successors.len(), 24);
+        for (action, position) in successors {
+            let ActionKind::Place(point) = action.action else {
+                panic!("only placements in the opening");
+            };
+            assert_eq!(position.board[point], Some(Piece::White));
+            assert_eq!(position.to_move, Player::Black);
+            assert_eq!(position.unplaced, [8, 9]);
+        }
+        assert_eq!(game.position(), Game::new().position());
+
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        let successors = game.successors();
+        assert_eq!(successors.len(), 2);
+        assert!(successors.iter().all(|(_, p)| p.must_remove.is_none()));
+        assert_eq!(game.move_log().len(), 5);
+    }
+
+    #[test]
+    fn test_closed_mills_match_board() {
+        let from_scratch = |game: &Game| -> Vec<[Point; 3]> {
+            game.variant()
+                .mills()
+                .iter()
+                .filter(|mill| {
+                    game.board[mill[0]].is_some()
+                        && mill.iter().all(|&p| game.board[p] == game.board[mill[0]])
+                })
+                .copied()
+                .collect()
+        };
+        random_positions(|game| {
+            assert_eq!(game.closed_mills(), from_scratch(game), "{game}");
+            let mut undone = game.clone();
+            if undone.undo().is_ok() {
+                assert_eq!(undone.closed_mills(), from_scratch(&undone), "{undone}");
+            }
+        });
+
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7"]);
+        assert_eq!(game.closed_mills(), [[6, 7, 0]]);
+        game.undo().unwrap();
+        assert!(game.closed_mills().is_empty());
+    }
+
+    #[test]
+    fn test_bitboards_match_board() {
+        let mut positions = 0;
+        random_positions(|game| {
+            positions += 1;
+            let variant = game.variant();
+            for color in [Color::White, Color::Black] {
+                let on_board: Vec<Point> =
+                    (0..24).filter(|&p| game.board[p] == Some(color)).collect();
+                let bits = on_board.iter().fold(0, |bits, p| bits | 1 << p);
+                assert_eq!(game.bits[Game::color_idx(color)], bits);
+                assert_eq!(game.count_pieces(color) as usize, on_board.len());
+
+                for point in 0..24 {
+                    let mill = variant.mills().iter().copied().find(|mill| {
+                        mill.contains(&point) && mill.iter().all(|&p| game.board[p] == Some(color))
+                    });
+                    assert_eq!(game.closed_mill_at(point, color), mill);
+                }
+
+                let can_move = if game.phase(color) == Phase::Movement {
+                    on_board.iter().any(|&from| {
+                        (0..24).any(|to| game.board[to].is_none() && variant.are_adjacent(from, to))
+                    })
+                } else {
+                    (0..variant.point_count()).any(|p| game.board[p].is_none())
+                };
+                assert_eq!(game.player_can_move(color), can_move);
+            }
+        });
+        assert!(positions > 1000);
+    }
+
+    #[test]
+    fn test_bitboards_survive_undo() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        let bits = game.bits;
+        play(&mut game, &["B P 4"]);
+        game.undo().unwrap();
+        assert_eq!(game.bits, bits);
+        game.undo().unwrap();
+        assert_eq!(game.bits[1], 1 << 3 | 1 << 4);
+    }
+
+    #[test]
+    fn test_positional_score() {
+        assert_eq!(Game::POINT_VALUES[0], 2);
+        assert_eq!(Game::POINT_VALUES[1], 3);
+        assert_eq!(Game::POINT_VALUES[9], 4);
+        assert_eq!(Game::POINT_VALUES.iter().sum::<i32>(), 2 * 32);
+
+        // a cross point of the middle square against a corner
+        let mut game = Game::new();
+        play(&mut game, &["W P 9", "B P 0"]);
+        assert_eq!(game.positional_score(Color::White), 4);
+        assert_eq!(game.positional_score(Color::Black), 2);
+
+        // the center of Three Men's Morris touches every other point
+        let mut game = Game::with_variant(Variant::Three);
+        play(&mut game, &["W P 8", "B P 0"]);
+        assert_eq!(game.positional_score(Color::White), 8);
+        assert_eq!(game.positional_score(Color::Black), 3);
+    }
+
+    #[test]
+    fn test_must_concede_mill() {
+        // White can still block Black's only threat at 2
+        let mut game = Game::new();
+        play(&mut game, &["W P 9", "B P 3", "W P 21", "B P 4"]);
+        assert!(!game.must_concede_mill());
+
+        // Black threatens 2 and 19 and White has no mill of its own to close first
+        play(&mut game, &["W P 23", "B P 11"]);
+        assert!(game.must_concede_mill());
+        play(&mut game, &["W P 2", "B P 19"]);
+        assert_eq!(game.must_remove, Some(Player::Black));
+
+        // White's mill 4-5-6 is boxed in, and its only move leaves 9 open for Black's piece on 10
+        let game = setup(&[9, 4, 5, 6], &[1, 17, 10, 3, 7, 13]);
+        assert_eq!(game.legal_moves(), ["W M 9 8".parse().unwrap()]);
+        assert!(game.must_concede_mill());
+
+        assert!(!Game::new().must_concede_mill());
+    }
+
+    #[test]
+    fn test_point_mills() {
+        for point in 0..24 {
+            for i in Game::POINT_MILLS[point] {
+                assert!(Game::MILLS[i].contains(&point));
+            }
+        }
+        assert_eq!(Game::POINT_MILLS[0], [0, 3]);
+        assert_eq!(Game::POINT_MILLS[9], [4, 12]);
+    }
+
+    #[test]
+    fn test_closed_mill_at_random_boards() {
+        let mut state: u64 = 99;
+        for variant in [Variant::Three, Variant::Six, Variant::Nine, Variant::Twelve] {
+            for _ in 0..500 {
+                let mut game = Game::with_variant(variant);
+                for point in 0..variant.point_count() {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    let piece =
+                        [None, Some(Color::White), Some(Color::Black)][(state >> 33) as usize % 3];
+                    game.set_point(point, piece);
+                }
+                for color in [Color::White, Color::Black] {
+                    for point in 0..variant.point_count() {
+                        let mill = variant.mills().iter().copied().find(|mill| {
+                            mill.contains(&point)
+                                && mill.iter().all(|&p| game.board[p] == Some(color))
+                        });
+                        assert_eq!(game.closed_mill_at(point, color), mill);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_legal_removals() {
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 5, 13, 21]);
+        assert!(game.legal_removals().is_empty());
+        // 5-13-21 is a mill, so only 3 and 4 can be taken
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.legal_removals(), [3, 4]);
+
+        // all black pieces are in mills, so every one can be taken
+        let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.legal_removals(), [5, 13, 21]);
+    }
+
+    #[test]
+    fn test_can_remove() {
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 5, 13, 21]);
+        assert!(!game.can_remove(3));
+        play(&mut game, &["W M 15 7"]);
+        assert!(game.can_remove(3));
+        // 5-13-21 is a mill
+        assert!(!game.can_remove(13));
+        // own pieces, empty points and points off the board
+        assert!(!game.can_remove(0));
+        assert!(!game.can_remove(1));
+        assert!(!game.can_remove(99));
+
+        // all black pieces are in mills
+        let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+        play(&mut game, &["W M 15 7"]);
+        assert!(game.can_remove(13));
+    }
+
+    #[test]
+    fn test_mill_removal_policy() {
+        // all black pieces are in the mill 5-13-21, and 6-7-0 closes with 15 to 7
+        let board = |policy| {
+            let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+            game.config.mill_removal_policy = policy;
+            game
+        };
+
+        let mut game = board(MillRemovalPolicy::Standard);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.legal_removals(), [5, 13, 21]);
+        assert!(game.can_remove(13));
+
+        let mut game = board(MillRemovalPolicy::AlwaysAllowed);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.legal_removals(), [5, 13, 21]);
+
+        let mut game = board(MillRemovalPolicy::NeverFromMill);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.position().must_remove, None);
+        assert_eq!(game.position().to_move, Player::Black);
+        assert!(!game.can_remove(13));
+
+        // with a piece outside the mills, only the standard rule protects the mill
+        let mut game = setup(&[0, 6, 15, 20], &[3, 5, 13, 21]);
+        game.config.mill_removal_policy = MillRemovalPolicy::AlwaysAllowed;
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.legal_removals(), [3, 5, 13, 21]);
+        game.config.mill_removal_policy = MillRemovalPolicy::NeverFromMill;
+        assert_eq!(game.legal_removals(), [3]);
+        assert_eq!(
+            game.action("W R 13".parse().unwrap()),
+            Err(ActionError::PieceInMill)
+        );
+        game.config.mill_removal_policy = MillRemovalPolicy::Standard;
+        assert_eq!(game.legal_removals(), [3]);
+    }
+
+    #[test]
+    fn test_mill_without_removal() {
+        // all black pieces are in the mill 5-13-21, which this policy protects
+        let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+        game.config.mill_removal_policy = MillRemovalPolicy::NeverFromMill;
+        assert!(!game.mill_without_removal());
+
+        // closing 6-7-0 passes the turn straight to Black
+        play(&mut game, &["W M 15 7"]);
+        assert!(game.mill_without_removal());
+        assert_eq!(game.last_mill(), Some([6, 7, 0]));
+        assert_eq!(game.to_move(), Player::Black);
+        play(&mut game, &["B M 5 1"]);
+        assert!(!game.mill_without_removal());
+        let log: Vec<String> = game.move_log().iter().map(|a| a.to_string()).collect();
+        assert_eq!(log, ["W M 15 7", "B M 5 1"]);
+
+        // a mill that leads to a removal
+        let mut game = setup(&[0, 6, 15, 20], &[5, 13, 21]);
+        play(&mut game, &["W M 15 7"]);
+        assert!(!game.mill_without_removal());
+        play(&mut game, &["W R 5"]);
+        assert!(!game.mill_without_removal());
+    }
+
+    #[test]
+    fn test_mill_points() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 1", "B P 4", "W P 2", "W R 4"],
+        );
+        // occupied and in White's mill 0-1-2
+        assert!(game.is_mill_point(0));
+        assert!(game.is_mill_point(2));
+        // occupied by a piece outside of any mill
+        assert!(!game.is_mill_point(3));
+        // empty points, e.g. 4 after its piece was removed
+        assert!(!game.is_mill_point(4));
+        assert!(!game.is_mill_point(7));
+
+        play(&mut game, &["B P 5", "W P 6"]);
+        assert!(game.would_form_mill(7, Color::White));
+        assert!(!game.would_form_mill(7, Color::Black));
+        // 4 lies on 2-3-4 and 4-5-6, both blocked by White
+        assert!(!game.would_form_mill(4, Color::Black));
+        // the piece already on 1 is ignored
+        assert!(game.would_form_mill(1, Color::White));
+        assert!(!game.would_form_mill(99, Color::White));
+    }
+
+    #[test]
+    fn test_out_of_range_points() {
+        let mut game = Game::new();
+        for action in ["W P 99", "W M 0 99", "W M 99 0", "W R 99"] {
+            let action: Action = action.parse().unwrap();
+            assert!(!game.is_legal(action));
+            assert!(game.action(action).is_err());
+        }
+        assert!(!game.is_blocked(99));
+        assert!(!game.is_mill_point(99));
+        assert!(!game.is_removable(99));
+        assert_eq!(game.closed_mill_at(99, Color::White), None);
+        assert!(Game::neighbors(99).is_empty());
+        assert!(Game::mills_containing(99).is_empty());
+        assert!(!Variant::Nine.are_adjacent(0, 99));
+        assert!(Variant::Nine.neighbors(99).is_empty());
+
+        // also during movement and while a removal is pending
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 5, 13]);
+        assert_eq!(
+            game.action("W M 15 99".parse().unwrap()),
+            Err(ActionError::PointOutOfRange)
+        );
+        assert_eq!(
+            game.action("W M 99 7".parse().unwrap()),
+            Err(ActionError::PointOutOfRange)
+        );
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(
+            game.action("W R 99".parse().unwrap()),
+            Err(ActionError::PointOutOfRange)
+        );
+        assert_eq!(game.move_log().len(), 1);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = Game::new().to_dot(1);
+        assert!(dot.starts_with("digraph game {\n"));
+        assert!(dot.ends_with("}\n"));
+        let nodes = dot.lines().filter(|line| line.contains("to move")).count();
+        assert_eq!(nodes, 25);
+        assert_eq!(dot.lines().filter(|line| line.contains(" -> ")).count(), 24);
+        let root = format!("{:016x}", Game::new().zobrist_hash());
+        assert!(dot.contains(&format!(
+            "\"{root}\" [label=\"{}\\nW to move\"]",
+            ".".repeat(24)
+        )));
+        assert!(dot.contains(&format!("\"{root}\" -> ")));
+        assert!(!dot.contains(&format!("-> \"{root}\"")));
+        assert!(dot.contains("[label=\"W P 23\"]"));
+
+        // three placements in Three Men's Morris: W P 0, B P 1, W P 2 and W P 2, B P 1, W P 0
+        // end in the same position
+        let dot = Game::with_variant(Variant::Three).to_dot(3);
+        assert_eq!(
+            dot.lines().filter(|line| line.contains(" -> ")).count(),
+            9 + 9 * 8 + 9 * 8 * 7
+        );
+        let nodes = dot.lines().filter(|line| line.contains("to move")).count();
+        assert_eq!(nodes, 1 + 9 + 9 * 8 + 9 * 28);
+        assert_eq!(Game::new().to_dot(0).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_move_to_same_point() {
+        let mut game = setup(&[5, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        let before = game.position();
+        assert_eq!(
+            game.action("W M 5 5".parse().unwrap()),
+            Err(ActionError::SamePoint)
+        );
+        assert_eq!(game.position(), before);
+        assert!(game.move_log().is_empty());
+        assert!(!game.is_legal("W M 5 5".parse().unwrap()));
+        // also while flying
+        let mut game = setup(&[5, 6, 15], &[3, 4, 10, 12, 17]);
+        assert_eq!(
+            game.action("W M 5 5".parse().unwrap()),
+            Err(ActionError::SamePoint)
+        );
+    }
+
+    #[test]
+    fn test_forbid_immediate_remill() {
+        // White opens 0-1-2 by moving 2 to 3 and wants to close it again next turn
+        let opened = |forbid: bool| {
+            let mut game = setup(&[0, 1, 2, 20], &[5, 10, 12, 14, 17]);
+            game.config.forbid_immediate_remill = forbid;
+            play(&mut game, &["W M 2 3", "B M 17 16"]);
+            game
+        };
+        let reclose: Action = "W M 3 2".parse().unwrap();
+
+        let mut game = opened(false);
+        assert!(game.legal_moves().contains(&reclose));
+        play(&mut game, &["W M 3 2"]);
+        assert_eq!(game.must_remove(), Some(Player::White));
+
+        let mut game = opened(true);
+        assert!(!game.legal_moves().contains(&reclose));
+        assert_eq!(game.action(reclose), Err(ActionError::ImmediateRemill));
+        assert!(game.is_legal("W M 3 4".parse().unwrap()));
+
+        // a move in between makes the mill fair game again
+        play(&mut game, &["W M 20 19", "B M 16 17", "W M 3 2"]);
+        assert_eq!(game.must_remove(), Some(Player::White));
+    }
+
+    #[test]
+    fn test_action_errors() {
+        let error = |game: &mut Game, action: &str| game.action(action.parse().unwrap()).err();
+
+        let mut game = Game::new();
+        assert_eq!(error(&mut game, "B P 0"), Some(ActionError::NotYourTurn));
+        assert_eq!(
+            error(&mut game, "W P 24"),
+            Some(ActionError::PointOutOfRange)
+        );
+        assert_eq!(error(&mut game, "W M 0 1"), Some(ActionError::StillPlacing));
+        assert_eq!(
+            error(&mut game, "W R 0"),
+            Some(ActionError::RemoveNotAllowed)
+        );
+        play(&mut game, &["W P 0"]);
+        assert_eq!(error(&mut game, "B P 0"), Some(ActionError::Occupied));
+
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        assert_eq!(error(&mut game, "W M 0 2"), Some(ActionError::NotAdjacent));
+        assert_eq!(
+            error(&mut game, "W M 1 2"),
+            Some(ActionError::NoPieceAtSource)
+        );
+        assert_eq!(error(&mut game, "W M 20 20"), Some(ActionError::SamePoint));
+        play(&mut game, &["W M 20 19"]);
+        assert_eq!(
+            error(&mut game, "B M 3 4"),
+            Some(ActionError::DestinationOccupied)
+        );
+
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(
+            error(&mut game, "B M 3 2"),
+            Some(ActionError::RemovalPending)
+        );
+        assert_eq!(error(&mut game, "W M 20 19"), Some(ActionError::MustRemove));
+        assert_eq!(
+            error(&mut game, "W R 0"),
+            Some(ActionError::NotOpponentPiece)
+        );
+
+        assert_eq!(Game::new().undo(), Err(ActionError::NothingToUndo));
+        assert_eq!(Game::new().redo(), Err(ActionError::NothingToRedo));
+
+        // the messages stay the same as before the error type
+        assert_eq!(ActionError::Occupied.to_string(), "Point already occupied");
+        assert_eq!(
+            ActionError::NotYourTurn.to_string(),
+            "Not this player's turn"
+        );
+        let boxed: Box<dyn std::error::Error> = Box::new(ActionError::NotAdjacent);
+        assert_eq!(boxed.to_string(), "Points not adjacent");
+        let message = ActionError::NothingToUndo.as_str();
+        assert_eq!(message, "No action to undo");
+    }
+
+    #[test]
+    fn test_rejected_actions_change_nothing() {
+        let check = |game: &mut Game, actions: &[&str]| {
+            for action in actions {
+                let before = game.clone();
+                assert!(game.action(action.parse().unwrap()).is_err(), "{action}");
+                assert_eq!(game.position(), before.position(), "{action}");
+                assert_eq!(game.bits, before.bits);
+                assert_eq!(game.mills, before.mills);
+                assert_eq!(game.history.len(), before.history.len());
+                assert_eq!(game.moves, before.moves);
+                assert_eq!(game.redo_stack.len(), before.redo_stack.len());
+            }
+        };
+
+        // placement, with an action to redo
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6"]);
+        game.undo().unwrap();
+        check(
+            &mut game,
+            &["W P 3", "B P 1", "W P 99", "W M 0 1", "W R 3", "W R 1"],
+        );
+
+        // movement
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        check(
+            &mut game,
+            &[
+                "B M 3 2",
+                "W M 0 2",
+                "W M 15 3",
+                "W M 1 2",
+                "W P 1",
+                "W R 3",
+                "W M 15 99",
+                "W M 15 15",
+            ],
+        );
+
+        // removal pending
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 5, 13, 21]);
+        play(&mut game, &["W M 15 7"]);
+        check(
+            &mut game,
+            &[
+                "W R 13",
+                "W R 0",
+                "W R 1",
+                "B R 0",
+                "W M 20 19",
+                "W P 1",
+                "W R 99",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_ordered_moves() {
+        // 15 -> 7 closes 6-7-0; moving to 11 blocks Black's 10-11-12
+        let game = setup(&[0, 6, 15, 19, 20], &[3, 4, 10, 12, 17]);
+        let ordered = game.ordered_moves();
+        assert_eq!(ordered[0], "W M 15 7".parse().unwrap());
+        assert_eq!(ordered[1], "W M 19 11".parse().unwrap());
+        assert_eq!(ordered.len(), game.legal_moves().len());
+        let mut sorted = ordered.clone();
+        sorted.sort();
+        let mut legal = game.legal_moves();
+        legal.sort();
+        assert_eq!(sorted, legal);
+
+        assert_eq!(Game::new().ordered_moves(), Game::new().legal_moves());
+    }
+
+    #[test]
+    fn test_forcing_moves() {
+        // only 15 -> 7 closes a mill; 0 -> 7 would leave the line 6-7-0
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        assert_eq!(game.forcing_moves(), ["W M 15 7".parse().unwrap()]);
+
+        play(&mut game, &["W M 15 7"]);
+        assert_eq!(game.forcing_moves(), game.legal_moves());
+        play(&mut game, &["W R 3"]);
+        assert!(game.forcing_moves().is_empty());
+
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 1", "B P 4"]);
+        assert_eq!(game.forcing_moves(), ["W P 2".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        let mut a = Game::new();
+        let mut b = Game::new();
+        play(&mut a, &["W P 0", "B P 9"]);
+        play(&mut b, &["W P 4", "B P 13"]);
+        assert_ne!(a.zobrist_hash(), b.zobrist_hash());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+        play(&mut b, &["W P 1"]);
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+    }
+
+    #[test]
+    fn test_black_moves_first() {
+        let mut game = Game::new_with_side_to_move(Player::Black);
+        assert_eq!(game.to_move(), Player::Black);
+        assert_eq!(
+            game.action("W P 0".parse().unwrap()),
+            Err(ActionError::NotYourTurn)
+        );
+        assert!(
+            game.legal_moves()
+                .iter()
+                .all(|action| action.player == Player::Black)
+        );
+
+        play(
+            &mut game,
+            &["B P 0", "W P 3", "B P 6", "W P 4", "B P 7", "B R 4"],
+        );
+        assert_eq!(game.to_move(), Player::White);
+        assert_eq!(game.validate(), Ok(()));
+        assert_eq!(game.winner(), None);
+        game.removed = [7, 0];
+        assert_eq!(game.winner(), Some(Player::Black));
+    }
+
+    #[test]
+    fn test_placements_remaining_total() {
+        let mut game = Game::new();
+        assert_eq!(game.placements_remaining_total(), 18);
+        assert!(!game.in_movement_phase());
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        assert_eq!(game.placements_remaining_total(), 13);
+
+        let mut game: Game = "W....................... B 0 1".parse().unwrap();
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert!(!game.in_movement_phase());
+        play(&mut game, &["B P 5"]);
+        assert_eq!(game.placements_remaining_total(), 0);
+        assert!(game.in_movement_phase());
+    }
+
+    #[test]
+    fn test_pieces_on_board_and_remaining() {
+        let mut game = Game::new();
+        assert_eq!(game.pieces_on_board(), (0, 0));
+        assert_eq!(game.pieces_remaining(Player::White), 9);
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        assert_eq!(game.pieces_on_board(), (3, 1));
+        assert_eq!(game.pieces_remaining(Player::White), 9);
+        assert_eq!(game.pieces_remaining(Player::Black), 8);
+
+        // movement phase: Black is one capture away from losing
+        let game = setup(&[0, 6, 15, 20], &[3, 10, 12]);
+        assert_eq!(game.pieces_on_board(), (4, 3));
+        assert_eq!(game.pieces_remaining(Player::White), 4);
+        assert_eq!(game.pieces_remaining(Player::Black), 3);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut game = Game::new();
+        for action in include_str!("../tests/example_games/game1.txt").lines() {
+            game.action(action.parse().unwrap()).unwrap();
+        }
+        let bytes = game.to_bytes();
+        assert_eq!(bytes.len(), 4 + 3 + 20 + 4 + 2 * game.move_log().len());
+        let restored = Game::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.move_log(), game.move_log());
+        assert_eq!(restored.position(), game.position());
+        assert_eq!(restored.winner(), Some(Player::White));
+
+        // a game that did not start from the empty board
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        game.config.stalemate_is_draw = true;
+        game.config.forbid_immediate_remill = true;
+        play(&mut game, &["W M 15 7"]);
+        let mut restored = Game::from_bytes(&game.to_bytes()).unwrap();
+        assert_eq!(restored.position(), game.position());
+        assert_eq!(restored.config(), game.config());
+        restored.undo().unwrap();
+        assert_eq!(restored.points()[15], Some(Piece::White));
+        assert!(restored.undo().is_err());
+    }
+
+    #[test]
+    fn test_bytes_reject_corrupt_data() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        let bytes = game.to_bytes();
+        for len in 0..bytes.len() {
+            assert!(Game::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let corrupt = |index: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[index] = value;
+            Game::from_bytes(&bytes).err()
+        };
+        assert_eq!(
+            corrupt(0, b'X'),
+            Some(LoadError::Parse("Not a game in binary format"))
+        );
+        assert_eq!(corrupt(4, 9), Some(LoadError::Parse("Invalid variant")));
+        assert_eq!(
+            corrupt(5, 3 << 3),
+            Some(LoadError::Parse("Invalid removal policy"))
+        );
+        assert_eq!(
+            corrupt(5, 1 << 6),
+            Some(LoadError::Parse("Invalid rule flags"))
+        );
+        assert_eq!(
+            corrupt(7, 1),
+            Some(LoadError::Position("Piece counts do not add up"))
+        );
+        assert_eq!(
+            corrupt(15, 6),
+            Some(LoadError::Parse("Invalid removal flag"))
+        );
+        // the first action becomes a placement on Black's point
+        assert_eq!(
+            corrupt(31, 3),
+            Some(LoadError::Action(ActionError::Occupied))
+        );
+        assert_eq!(
+            corrupt(31, 3 << 5),
+            Some(LoadError::Parse("Invalid action"))
+        );
+
+        let mut bytes = bytes.clone();
+        bytes.push(0);
+        assert_eq!(
+            Game::from_bytes(&bytes).err(),
+            Some(LoadError::Parse("Trailing data"))
+        );
+    }
+
+    #[test]
+    fn test_undo_n() {
+        let mut game = Game::new();
+        play(&mut game, &["W P 0", "B P 3", "W P 6", "B P 4", "W P 9"]);
+        assert_eq!(game.undo_n(3), Ok(3));
+        assert_eq!(game.move_log().len(), 2);
+        assert_eq!(game.points()[3], Some(Piece::Black));
+        assert_eq!(game.points()[6], None);
+
+        assert_eq!(game.undo_n(0), Ok(0));
+        assert_eq!(game.undo_n(100), Ok(2));
+        assert_eq!(game.points(), Game::new().points());
+        assert_eq!(game.undo_n(1), Err(ActionError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        let played = game.position();
+        game.reset();
+        assert_eq!(game.position(), Game::new().position());
+        assert!(game.move_log().is_empty());
+
+        // the undone actions can still be replayed
+        while game.redo().is_ok() {}
+        assert_eq!(game.position(), played);
+        assert_eq!(game.move_log().len(), 6);
+    }
+
+    #[test]
+    fn test_game_builder() {
+        // Black is down to three pieces and White closes 6-7-0 by moving 15 to 7
+        let mut game = GameBuilder::new()
+            .place(0, Color::White)
+            .place(6, Color::White)
+            .place(15, Color::White)
+            .place(20, Color::White)
+            .place(3, Color::Black)
+            .place(10, Color::Black)
+            .place(12, Color::Black)
+            .removed(Color::White, 5)
+            .removed(Color::Black, 6)
+            .build()
+            .unwrap();
+        assert_eq!(game.phase(Player::White), Phase::Movement);
+        assert_eq!(game.phase(Player::Black), Phase::Flying);
+        assert_eq!(game.winner(), None);
+        play(&mut game, &["W M 15 7", "W R 3"]);
+        assert_eq!(game.winner(), Some(Player::White));
+
+        // placement phase: the unplaced pieces are derived from the board
+        let game = GameBuilder::new()
+            .place(0, Color::White)
+            .to_move(Player::Black)
+            .build()
+            .unwrap();
+        assert_eq!(game.position().unplaced, [8, 9]);
+
+        assert_eq!(
+            GameBuilder::new().place(99, Color::White).build().err(),
+            Some("Piece outside the board")
+        );
+        assert_eq!(
+            GameBuilder::new().removed(Color::Black, 10).build().err(),
+            Some("Piece counts do not add up")
+        );
+        assert_eq!(
+            GameBuilder::new().place(0, Color::Black).build().err(),
+            Some("Players did not place alternately")
+        );
+        assert_eq!(
+            GameBuilder::new()
+                .variant(Variant::Three)
+                .place(20, Color::White)
+                .build()
+                .err(),
+            Some("Piece outside the board")
+        );
+        assert_eq!(
+            GameBuilder::new()
+                .must_remove(Some(Player::White))
+                .build()
+                .err(),
+            Some("Removal pending without a mill")
+        );
+    }
+
+    #[test]
+    fn test_no_legal_moves_after_game_over() {
+        // White has taken seven pieces; Black could still move 3 to 2
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10]);
+        game.removed[1] = 7;
+        game.to_move = Player::Black;
+        assert!(game.is_game_over());
+        assert_eq!(game.win_reason(), Some(WinReason::SevenRemoved));
+        assert!(game.legal_moves().is_empty());
+
+        // Black's corners are boxed in by White's midpoints
+        let mut game = setup(&[1, 3, 5, 7], &[0, 2, 4, 6]);
+        game.to_move = Player::Black;
+        assert!(game.is_game_over());
+        assert_eq!(game.win_reason(), Some(WinReason::NoLegalMoves));
+        assert!(game.legal_moves().is_empty());
+
+        let mut game = Game::new();
+        assert!(!game.is_game_over());
+        play(&mut game, &["W P 0"]);
+        assert!(!game.is_game_over());
+        assert_eq!(game.legal_moves().len(), 23);
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut game = Game::new();
+        let mut notations = Vec::new();
+        for action in ["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"] {
+            let action: Action = action.parse().unwrap();
+            let notation = game.to_notation(&action);
+            assert_eq!(game.from_notation(&notation), Ok(action));
+            game.action(action).unwrap();
+            notations.push(notation);
+        }
+        assert_eq!(notations.concat(), "a7g4a1g1a4xg1");
+
+        let mut game = setup(&[0, 6, 15, 20], &[3, 4, 10, 12, 17]);
+        let action: Action = "W M 15 7".parse().unwrap();
+        assert_eq!(game.to_notation(&action), "b4-a4x");
+        assert_eq!(game.from_notation("b4-a4x"), Ok(action));
+        assert_eq!(game.from_notation("B4-A4"), Ok(action));
+        game.action(action).unwrap();
+        assert_eq!(game.to_notation(&"W R 3".parse().unwrap()), "g4");
+        assert_eq!(game.from_notation("g4"), Ok("W R 3".parse().unwrap()));
+
+        assert_eq!(
+            game.from_notation("h9"),
+            Err(LoadError::Parse("Invalid coordinate"))
+        );
+        assert_eq!(
+            game.from_notation("a7-"),
+            Err(LoadError::Parse("Invalid coordinate"))
+        );
+        assert_eq!(
+            game.from_notation(""),
+            Err(LoadError::Parse("Invalid coordinate"))
+        );
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &["W P 0", "B P 3", "W P 6", "B P 4", "W P 7", "W R 4"],
+        );
+        let metadata = Metadata {
+            white: "Alice \"Ace\"".to_string(),
+            black: "Bob\\\r\nJr.".to_string(),
+            date: "2024-05-01".to_string(),
+            result: GameResult::Win(Player::White),
+        };
+        let record = game.to_record(&metadata);
+        let mut lines = record.lines();
+        assert_eq!(lines.next(), Some("[White \"Alice \\\"Ace\\\"\"]"));
+        // line breaks are escaped, so every header stays on one line
+        assert_eq!(lines.next(), Some("[Black \"Bob\\\\\\r\\nJr.\"]"));
+        assert_eq!(lines.next(), Some("[Date \"2024-05-01\"]"));
+        assert_eq!(lines.next(), Some("[Result \"1-0\"]"));
+        assert_eq!(lines.nth(1), Some("1. W P 0"));
+        assert!(record.ends_with("6. W R 4\n"));
+
+        let (parsed, parsed_metadata) = Game::from_record(&record).unwrap();
+        assert_eq!(parsed.move_log(), game.move_log());
+        assert_eq!(parsed.position(), game.position());
+        assert_eq!(parsed_metadata, metadata);
+
+        let draw = Game::new().to_record_today("A", "B", GameResult::Draw(DrawReason::NoProgress));
+        let (_, metadata) = Game::from_record(&draw).unwrap();
+        assert_eq!(metadata.result, GameResult::Draw(DrawReason::NoProgress));
+        assert_eq!(metadata.date.len(), 10);
+
+        let broken = record.replace("2. B P 3", "3. B P 3");
+        assert_eq!(
+            Game::from_record(&broken).err(),
+            Some(LoadError::Parse("Invalid record move"))
+        );
+        let broken = record.replace("[Date", "[Event \"League\"]\n[Datum");
+        assert_eq!(
+            Game::from_record(&broken).err(),
+            Some(LoadError::Parse("Missing record header"))
+        );
+        let broken = record.replace("1-0", "2-0");
+        assert_eq!(
+            Game::from_record(&broken).err(),
+            Some(LoadError::Parse("Invalid record result"))
+        );
+        // a finished game must carry its own result
+        let mut finished = Game::new();
+        while !finished.is_game_over() {
+            let action = finished.legal_moves()[0];
+            finished.action(action).unwrap();
+        }
+        let mut metadata = Metadata {
+            result: finished.result(),
+            ..metadata
+        };
+        assert!(Game::from_record(&finished.to_record(&metadata)).is_ok());
+        for result in [GameResult::Ongoing, GameResult::Draw(DrawReason::Stalemate)] {
+            metadata.result = result;
+            assert_eq!(
+                Game::from_record(&finished.to_record(&metadata)).err(),
+                Some(LoadError::ResultMismatch)
+            );
+        }
+
+        let broken = record.replace("B P 3", "B P 0");
+        assert_eq!(
+            Game::from_record(&broken).err(),
+            Some(LoadError::Action(ActionError::Occupied))
+        );
     }
 }
@@ -0,0 +1,102 @@
+// Line based text protocol for driving the engine from another process
+// Every command is one line, every response starts with `ok` or `error`.
+// Responses with data put it after `ok`; the board takes several lines.
+//
+// Commands:
+//   new            start a new standard game
+//   move <action>  apply an action, e.g. `move W P 0`
+//   undo           undo the last action
+//   legal          list the legal actions, separated by commas
+//   board          draw the board
+//   result         `ongoing`, `win W`, `win B` or `draw <reason>`
+
+use crate::nmm::{Action, ActionError, DrawReason, Game, GameResult, NmmGame, Player};
+
+/// Executes one command on `game` and returns the response.
+/// Empty lines and unknown commands are answered with an error.
+pub fn handle_line(game: &mut Game, line: &str) -> String {
+    let line = line.trim();
+    let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+    let response = match command {
+        "new" => {
+            *game = Game::new();
+            Ok(String::new())
+        }
+        "move" => args
+            .parse::<Action>()
+            .and_then(|action| game.action(action).map_err(ActionError::as_str))
+            .map(|_| String::new()),
+        "undo" => game
+            .undo()
+            .map(|_| String::new())
+            .map_err(ActionError::as_str),
+        "legal" => Ok(game
+            .legal_moves()
+            .iter()
+            .map(|action| action.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")),
+        "board" => Ok(format!("\n{game}")),
+        "result" => Ok(match game.result() {
+            GameResult::Ongoing => "ongoing".to_string(),
+            GameResult::Win(Player::White) => "win W".to_string(),
+            GameResult::Win(Player::Black) => "win B".to_string(),
+            GameResult::Draw(DrawReason::Repetition) => "draw repetition".to_string(),
+            GameResult::Draw(DrawReason::NoProgress) => "draw no-progress".to_string(),
+            GameResult::Draw(DrawReason::Stalemate) => "draw stalemate".to_string(),
+        }),
+        _ => Err("Unknown command"),
+    };
+    match response {
+        Ok(data) if data.is_empty() || data.starts_with('\n') => format!("ok{data}"),
+        Ok(data) => format!("ok {data}"),
+        Err(err) => format!("error {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_session() {
+        let mut game = Game::new();
+        let session = [
+            ("move W P 0", "ok"),
+            ("move B P 0", "error Point already occupied"),
+            ("move B P", "error Invalid action format"),
+            ("move B P 3", "ok"),
+            ("result", "ok ongoing"),
+            ("undo", "ok"),
+            ("undo", "ok"),
+            ("undo", "error No action to undo"),
+            ("fly W 0", "error Unknown command"),
+            ("", "error Unknown command"),
+        ];
+        for (command, expected) in session {
+            assert_eq!(handle_line(&mut game, command), expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_legal_board_and_result() {
+        let mut game = Game::new();
+        for command in [
+            "move W P 0",
+            "move B P 3",
+            "move W P 6",
+            "move B P 4",
+            "move W P 7",
+        ] {
+            assert_eq!(handle_line(&mut game, command), "ok");
+        }
+        assert_eq!(handle_line(&mut game, "legal"), "ok W R 3, W R 4");
+        assert_eq!(handle_line(&mut game, "board"), format!("ok\n{game}"));
+        assert!(handle_line(&mut game, "board").starts_with("ok\nW-----.-----."));
+
+        let mut won: Game = "WW.B.B.B..............B. B".parse().unwrap();
+        assert_eq!(handle_line(&mut won, "result"), "ok win B");
+        assert_eq!(handle_line(&mut won, "new"), "ok");
+        assert_eq!(won.points(), Game::new().points());
+    }
+}
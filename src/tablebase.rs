@@ -0,0 +1,336 @@
+// Endgame tablebases built by retrograde analysis
+// A tablebase covers every position of one rule set in which both players have placed all their
+// pieces, have `pieces` of them on the board and no removal is pending. It only supports endings
+// that the next capture decides, e.g. three against three in Nine Men's Morris (both flying) or
+// the movement phase of Three Men's Morris, so every position outside of it is a finished game.
+//
+// Results are for the side to move and count plies until the game ends with best play,
+// with removals as plies of their own. Draws by repetition or without progress are not applied.
+
+use std::collections::HashMap;
+
+use crate::nmm::{ActionError, Color, Game, GameConfig, GameResult, NmmGame, Position};
+
+/// The value of a position for the side to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TbResult {
+    /// Wins in this many plies, as fast as possible.
+    Win(u32),
+    /// Loses in this many plies, as slowly as possible.
+    Loss(u32),
+    Draw,
+}
+
+impl TbResult {
+    // the value one ply earlier, for the player who moved into this position
+    fn before(self, same_player: bool) -> TbResult {
+        match (self, same_player) {
+            (TbResult::Win(plies), true) | (TbResult::Loss(plies), false) => {
+                TbResult::Win(plies + 1)
+            }
+            (TbResult::Loss(plies), true) | (TbResult::Win(plies), false) => {
+                TbResult::Loss(plies + 1)
+            }
+            (TbResult::Draw, _) => TbResult::Draw,
+        }
+    }
+
+    // orders results from the best to the worst for the side to move
+    fn rank(self) -> (u8, i64) {
+        match self {
+            TbResult::Win(plies) => (0, plies as i64),
+            TbResult::Draw => (1, 0),
+            TbResult::Loss(plies) => (2, -(plies as i64)),
+        }
+    }
+}
+
+/// The results of all positions of one endgame. See the top of this module.
+#[derive(Clone, Debug)]
+pub struct Tablebase {
+    config: GameConfig,
+    pieces: u8,
+    results: HashMap<u64, TbResult>,
+}
+
+impl Tablebase {
+    /// Solves the endgame with `pieces` pieces per player on the board under `config`.
+    /// Fails if a capture would not end the game, since those positions are not covered.
+    /// Three against three in Nine Men's Morris has over five million positions; in a release
+    /// build it took 8 to 9 minutes and peaked at about 270 MB of memory.
+    pub fn generate(config: GameConfig, pieces: u8) -> Result<Tablebase, &'static str> {
+        let total = config.variant.pieces();
+        if pieces == 0 || pieces > total || total - pieces + 1 < config.loss_threshold {
+            return Err("Tablebase only covers endings decided by the next capture");
+        }
+        let mut tablebase = Tablebase {
+            config,
+            pieces,
+            results: HashMap::new(),
+        };
+
+        // only the keys of unsolved positions are kept, games are set up again for each sweep
+        let masks = subsets(config.variant.point_count(), pieces);
+        let mut unsolved = Vec::new();
+        for &white in &masks {
+            for &black in masks.iter().filter(|&&black| black & white == 0) {
+                for to_move in [0, 1] {
+                    let key = to_move << 48 | (black as u64) << 24 | white as u64;
+                    tablebase.game(key)?;
+                    unsolved.push(key);
+                }
+            }
+        }
+
+        // Sweep over the unsolved positions until nothing changes. Positions solved in sweep `n`
+        // only become visible in sweep `n + 1`, and every position that ends within `n` plies is
+        // solved by sweep `n`. So an unsolved successor takes at least `n + 1` plies to win
+        // through, and wins up to that length are final.
+        for sweep in 1.. {
+            let mut solved = Vec::new();
+            let mut deferred = false;
+            unsolved.retain(|&key| {
+                let mut game = tablebase.game(key).expect("checked before the first sweep");
+                match tablebase.evaluate(&mut game) {
+                    Ok(Some(TbResult::Win(plies))) if plies > sweep + 1 => {
+                        deferred = true;
+                        true
+                    }
+                    Ok(Some(result)) => {
+                        solved.push((key, result));
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            if solved.is_empty() && !deferred {
+                break;
+            }
+            tablebase.results.extend(solved);
+        }
+        // nothing forces a result in the remaining positions
+        for key in unsolved {
+            tablebase.results.insert(key, TbResult::Draw);
+        }
+        Ok(tablebase)
+    }
+
+    /// Returns the number of positions in the tablebase.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns true if the tablebase has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the result of `position`, or `None` if the tablebase does not cover it.
+    /// The history of the position, e.g. `quiet_moves`, is ignored.
+    pub fn probe(&self, position: &Position) -> Option<TbResult> {
+        if position.config != self.config
+            || position.must_remove.is_some()
+            || position.unplaced != [0, 0]
+        {
+            return None;
+        }
+        self.results.get(&key(position)).copied()
+    }
+
+    // the game of a position given by its `key`
+    fn game(&self, key: u64) -> Result<Game, &'static str> {
+        let total = self.config.variant.pieces();
+        Game::from_position(Position {
+            board: std::array::from_fn(|p| {
+                if key & 1 << p != 0 {
+                    Some(Color::White)
+                } else if key & 1 << (p + 24) != 0 {
+                    Some(Color::Black)
+                } else {
+                    None
+                }
+            }),
+            to_move: if key >> 48 != 0 {
+                Color::Black
+            } else {
+                Color::White
+            },
+            unplaced: [0, 0],
+            removed: [total - self.pieces; 2],
+            must_remove: None,
+            last_mill: None,
+            quiet_moves: 0,
+            config: self.config,
+        })
+    }
+
+    // The value of `game`, or `None` while it depends on unsolved positions.
+    // Wins are reported as soon as one is known, see `generate`.
+    fn evaluate(&self, game: &mut Game) -> Result<Option<TbResult>, &'static str> {
+        match finished(game) {
+            Some(result) => Ok(Some(result)),
+            None => self.solve(game),
+        }
+    }
+
+    // the value of `game` from its successors, like `evaluate`
+    fn solve(&self, game: &mut Game) -> Result<Option<TbResult>, &'static str> {
+        let to_move = game.position().to_move;
+        let mut best: Option<TbResult> = None;
+        let mut complete = true;
+        for action in game.legal_moves() {
+            game.action(action).map_err(ActionError::as_str)?;
+            let child = self.lookup(game);
+            let same_player = game.position().to_move == to_move;
+            game.undo().map_err(ActionError::as_str)?;
+            match child? {
+                Some(result) => {
+                    let result = result.before(same_player);
+                    if best.is_none_or(|best| result.rank() < best.rank()) {
+                        best = Some(result);
+                    }
+                }
+                None => complete = false,
+            }
+        }
+        Ok(match best {
+            Some(TbResult::Win(plies)) => Some(TbResult::Win(plies)),
+            _ if complete => best,
+            _ => None,
+        })
+    }
+
+    // the value of a successor: finished, covered by the tablebase or a pending removal
+    fn lookup(&self, game: &mut Game) -> Result<Option<TbResult>, &'static str> {
+        let position = game.position();
+        if finished(game).is_some() || position.must_remove.is_some() {
+            return self.evaluate(game);
+        }
+        let on_board = |color| position.board.iter().filter(|&&p| p == Some(color)).count();
+        if on_board(Color::White) != self.pieces as usize
+            || on_board(Color::Black) != self.pieces as usize
+        {
+            return Err("Position outside the tablebase");
+        }
+        Ok(self.results.get(&key(&position)).copied())
+    }
+}
+
+// the value of a finished game for the side to move
+fn finished(game: &Game) -> Option<TbResult> {
+    match game.result() {
+        GameResult::Win(winner) if winner == game.position().to_move => Some(TbResult::Win(0)),
+        GameResult::Win(_) => Some(TbResult::Loss(0)),
+        GameResult::Draw(_) => Some(TbResult::Draw),
+        GameResult::Ongoing => None,
+    }
+}
+
+// the board and side to move, packed as White's points, Black's points and a bit for Black to move
+fn key(position: &Position) -> u64 {
+    let mut key = ((position.to_move == Color::Black) as u64) << 48;
+    for (p, piece) in position.board.iter().enumerate() {
+        match piece {
+            Some(Color::White) => key |= 1 << p,
+            Some(Color::Black) => key |= 1 << (p + 24),
+            None => {}
+        }
+    }
+    key
+}
+
+// all sets of `size` points among the first `count` as bitmasks
+fn subsets(count: usize, size: u8) -> Vec<u32> {
+    (0..1u32 << count)
+        .filter(|mask| mask.count_ones() == size as u32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nmm::{Point, Variant};
+
+    fn three_mens() -> Tablebase {
+        Tablebase::generate(GameConfig::for_variant(Variant::Three), 3).unwrap()
+    }
+
+    fn position(white: &[Point], black: &[Point], to_move: Color) -> Position {
+        let mut position = Game::with_variant(Variant::Three).position();
+        for &p in white {
+            position.board[p] = Some(Color::White);
+        }
+        for &p in black {
+            position.board[p] = Some(Color::Black);
+        }
+        position.unplaced = [0, 0];
+        position.to_move = to_move;
+        position
+    }
+
+    #[test]
+    fn test_three_mens_endgame() {
+        let tablebase = three_mens();
+        // 3 of 9 points for White, 3 of the other 6 for Black, either side to move
+        assert_eq!(tablebase.len(), 84 * 20 * 2);
+
+        // White closes 0-1-2 by moving the center piece up and removes a piece
+        let won = position(&[0, 2, 8], &[3, 4, 5], Color::White);
+        assert_eq!(tablebase.probe(&won), Some(TbResult::Win(2)));
+
+        // not covered: placement phase and other rules
+        assert_eq!(
+            tablebase.probe(&Game::with_variant(Variant::Three).position()),
+            None
+        );
+        assert_eq!(tablebase.probe(&Game::new().position()), None);
+    }
+
+    #[test]
+    fn test_results_agree_with_successors() {
+        let tablebase = three_mens();
+        for (&key, &result) in &tablebase.results {
+            let mut game = tablebase.game(key).unwrap();
+            assert_eq!(tablebase.evaluate(&mut game), Ok(Some(result)), "{game}");
+            assert!(game.undo().is_err());
+        }
+    }
+
+    // takes 8 to 9 minutes, run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_nine_mens_flying_endgame() {
+        let config = GameConfig::for_variant(Variant::Nine);
+        let tablebase = Tablebase::generate(config, 3).unwrap();
+        // 3 of 24 points for White, 3 of the other 21 for Black, either side to move
+        assert_eq!(tablebase.len(), 2024 * 1330 * 2);
+
+        let mut position = Game::new().position();
+        position.unplaced = [0, 0];
+        position.removed = [6, 6];
+        let mut at = |white: [Point; 3], black: [Point; 3], to_move| {
+            position.board = [None; 24];
+            for p in white {
+                position.board[p] = Some(Color::White);
+            }
+            for p in black {
+                position.board[p] = Some(Color::Black);
+            }
+            position.to_move = to_move;
+            position
+        };
+        // White flies 23 to 2, closes 0-1-2 and removes a piece
+        let won = at([0, 1, 23], [9, 12, 20], Color::White);
+        assert_eq!(tablebase.probe(&won), Some(TbResult::Win(2)));
+        // White threatens 0-1-2 and 6-7-0 at once, Black can only block one of them
+        let lost = at([0, 1, 6], [9, 12, 20], Color::Black);
+        assert_eq!(tablebase.probe(&lost), Some(TbResult::Loss(3)));
+    }
+
+    #[test]
+    fn test_generate_rejects_uncovered_endings() {
+        let nine = GameConfig::for_variant(Variant::Nine);
+        assert!(Tablebase::generate(nine, 4).is_err());
+        assert!(Tablebase::generate(nine, 0).is_err());
+    }
+}